@@ -1,59 +1,321 @@
 // src/actions/add.rs
-use crate::utils::{find_project_root_by_marker, run_command};
-use crate::vcpkg::{self, VCPKG_JSON_FILENAME};
+use crate::cmake::{
+    self, DependencyEntry, LinkTarget, ManagedCMakeLists, CMAKELISTS_FILENAME,
+};
+use crate::config::ProjectConfig;
+use crate::manifest::{Dependency, DependencyDetails, FeatureRef, VcpkgManifest};
+use crate::usage;
+use crate::utils::{self, unified_diff};
+use crate::vcpkg::{self, VcpkgPaths, VCPKG_JSON_FILENAME};
 use anyhow::{Context, Result, bail};
+use std::fs;
+use std::path::Path;
+
+// Adds find_package/target_link_libraries lines for the new ports to the
+// rig-managed block of CMakeLists.txt. Without the block markers, the proposed
+// change is only shown as a diff unless `force` is set.
+fn update_cmakelists(project_root: &Path, ports: &[String], force: bool) -> Result<()> {
+    let cmakelists_path = project_root.join(CMAKELISTS_FILENAME);
+    if !cmakelists_path.exists() {
+        println!("No {} found; skipping CMake update.", CMAKELISTS_FILENAME);
+        return Ok(());
+    }
+    let content = fs::read_to_string(&cmakelists_path)
+        .with_context(|| format!("Failed to read {:?}", cmakelists_path))?;
+
+    let target = LinkTarget::detect(&content).with_context(|| {
+        format!(
+            "Could not find an add_executable/add_library target in {:?} to link dependencies to",
+            cmakelists_path
+        )
+    })?;
+
+    match ManagedCMakeLists::parse(&content)
+        .with_context(|| format!("Cannot update {:?}", cmakelists_path))?
+    {
+        Some(mut managed) => {
+            let mut added = Vec::new();
+            for port in ports {
+                if managed.contains(port) {
+                    println!("'{}' is already listed in {}.", port, CMAKELISTS_FILENAME);
+                    continue;
+                }
+                let package = usage::resolve_package(project_root, port);
+                managed.add(DependencyEntry::new(port, &package, &target));
+                added.push(port.as_str());
+            }
+            if !added.is_empty() {
+                utils::write(&cmakelists_path, managed.render())
+                    .with_context(|| format!("Failed to write {:?}", cmakelists_path))?;
+                println!(
+                    "Updated {} (linked {:?} to target '{}').",
+                    CMAKELISTS_FILENAME, added, target.name
+                );
+            }
+        }
+        None => {
+            let entries: Vec<DependencyEntry> = ports
+                .iter()
+                .map(|port| {
+                    let package = usage::resolve_package(project_root, port);
+                    DependencyEntry::new(port, &package, &target)
+                })
+                .collect();
+            let proposed = cmake::append_dependency_block(&content, &entries);
+            println!(
+                "{} has no rig-managed dependency block. Proposed change:\n",
+                CMAKELISTS_FILENAME
+            );
+            print!("{}", unified_diff(&content, &proposed, CMAKELISTS_FILENAME));
+            if force {
+                utils::write(&cmakelists_path, &proposed)
+                    .with_context(|| format!("Failed to write {:?}", cmakelists_path))?;
+                println!("\nUpdated {} (--force).", CMAKELISTS_FILENAME);
+            } else {
+                println!(
+                    "\n{} was not modified. Re-run with --force to apply this change.",
+                    CMAKELISTS_FILENAME
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+// Adds find_dependency lines for the new ports to the rig-managed block of a
+// library's `<name>Config.cmake.in`, so consumers of the installed package
+// find them too. Projects without the template (executables) are left alone.
+fn update_package_config(config: &ProjectConfig, ports: &[String]) -> Result<()> {
+    let template_path = cmake::package_config_template_path(config);
+    if !template_path.exists() {
+        return Ok(());
+    }
+    let content = fs::read_to_string(&template_path)
+        .with_context(|| format!("Failed to read {:?}", template_path))?;
+    let Some(mut managed) = ManagedCMakeLists::parse(&content)
+        .with_context(|| format!("Cannot update {:?}", template_path))?
+    else {
+        println!(
+            "Warning: {:?} has no rig-managed dependency block; add find_dependency calls for {:?} by hand.",
+            template_path, ports
+        );
+        return Ok(());
+    };
+
+    let mut added = Vec::new();
+    for port in ports {
+        if managed.contains(port) {
+            continue;
+        }
+        let package = usage::resolve_package(&config.project_path, port);
+        managed.add(DependencyEntry::find_dependency(port, &package));
+        added.push(port.as_str());
+    }
+    if !added.is_empty() {
+        utils::write(&template_path, managed.render())
+            .with_context(|| format!("Failed to write {:?}", template_path))?;
+        let relative = template_path.strip_prefix(&config.project_path).unwrap_or(&template_path);
+        println!("Updated {} (added find_dependency for {:?}).", relative.display(), added);
+    }
+    Ok(())
+}
+
+/// A dependency as written on the command line: `port[feature,...]@version`.
+struct DependencySpec {
+    port: String,
+    features: Vec<String>,
+    version: Option<String>,
+}
+
+impl DependencySpec {
+    fn parse(spec: &str) -> Result<Self> {
+        let (name_part, version) = match spec.split_once('@') {
+            Some((name, version)) if !version.is_empty() => (name, Some(version.to_string())),
+            Some(_) => bail!("Missing version after '@' in '{}'", spec),
+            None => (spec, None),
+        };
+        let (port, features) = match name_part.split_once('[') {
+            Some((port, rest)) => {
+                let list = rest
+                    .strip_suffix(']')
+                    .with_context(|| format!("Missing closing ']' in '{}'", spec))?;
+                let features = list
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|f| !f.is_empty())
+                    .map(String::from)
+                    .collect();
+                (port, features)
+            }
+            None => (name_part, Vec::new()),
+        };
+        if port.is_empty()
+            || !port
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        {
+            bail!(
+                "Invalid port name '{}' (expected lowercase letters, digits and '-')",
+                port
+            );
+        }
+        Ok(Self {
+            port: port.to_string(),
+            features,
+            version,
+        })
+    }
+
+    fn to_dependency(&self, no_default_features: bool, platform: Option<&str>) -> Dependency {
+        if self.features.is_empty()
+            && self.version.is_none()
+            && !no_default_features
+            && platform.is_none()
+        {
+            return Dependency::Name(self.port.clone());
+        }
+        Dependency::Detailed(DependencyDetails {
+            name: self.port.clone(),
+            features: (!self.features.is_empty())
+                .then(|| self.features.iter().cloned().map(FeatureRef::Name).collect()),
+            default_features: no_default_features.then_some(false),
+            platform: platform.map(String::from),
+            version_ge: self.version.clone(),
+            ..Default::default()
+        })
+    }
+
+    fn is_qualified(&self) -> bool {
+        !self.features.is_empty() || self.version.is_some()
+    }
+}
+
+// Checks the port, its features and the requested version against the local
+// vcpkg checkout before anything is written.
+fn verify_against_checkout(vcpkg_paths: &VcpkgPaths, spec: &DependencySpec) -> Result<()> {
+    let port_manifest = vcpkg::port_manifest(vcpkg_paths, &spec.port)?;
+
+    let available: Vec<&String> = port_manifest
+        .features
+        .as_ref()
+        .map(|features| features.keys().collect())
+        .unwrap_or_default();
+    for feature in &spec.features {
+        if feature != "core" && feature != "default" && !available.contains(&feature) {
+            bail!(
+                "Port '{}' has no feature '{}'. Available features: {}",
+                spec.port,
+                feature,
+                if available.is_empty() {
+                    "(none)".to_string()
+                } else {
+                    available.iter().map(|f| f.as_str()).collect::<Vec<_>>().join(", ")
+                }
+            );
+        }
+    }
+
+    if let Some(requested) = &spec.version {
+        let (version, port_version) = match requested.split_once('#') {
+            Some((version, port_version)) => (
+                version,
+                Some(port_version.parse::<u32>().with_context(|| {
+                    format!("Invalid port-version in '{}'", requested)
+                })?),
+            ),
+            None => (requested.as_str(), None),
+        };
+        let known = vcpkg::port_versions(vcpkg_paths, &spec.port)?;
+        let found = known.iter().any(|v| {
+            v.version == version && port_version.is_none_or(|pv| pv == v.port_version)
+        });
+        if !found {
+            let recent: Vec<String> = known.iter().take(5).map(|v| v.to_string()).collect();
+            bail!(
+                "Version '{}' of port '{}' is not in the vcpkg versions database. Most recent versions: {}",
+                requested,
+                spec.port,
+                recent.join(", ")
+            );
+        }
+    }
+    Ok(())
+}
 
 pub fn add_dependencies(
+    config: &ProjectConfig,
     dependencies_to_add: &[String],
-    vcpkg_root_override: Option<String>,
+    no_default_features: bool,
+    platform: Option<String>,
+    force: bool,
 ) -> Result<()> {
     if dependencies_to_add.is_empty() {
         bail!("No dependencies specified to add.");
     }
 
     println!("Attempting to add dependencies: {:?}", dependencies_to_add);
+    let specs = dependencies_to_add
+        .iter()
+        .map(|spec| DependencySpec::parse(spec))
+        .collect::<Result<Vec<_>>>()?;
+
+    // 1. The project root comes from rig.toml
+    let project_root = &config.project_path;
 
-    // 1. Find project root (where vcpkg.json should be)
-    let project_root = find_project_root_by_marker(VCPKG_JSON_FILENAME)
-        .context("Failed to find project root. Are you in a rig-managed project (look for vcpkg.json)?")?;
-    
     println!("Operating in project root: {}", project_root.display());
 
-    // 2. Locate vcpkg executable
-    let vcpkg_paths = vcpkg::locate_and_verify_vcpkg(vcpkg_root_override)
-        .context("Failed to locate vcpkg.")?;
+    // 2. Locate the vcpkg checkout, which is used to validate the requests
+    let vcpkg_paths = config.vcpkg()?;
 
-    // 3. Ensure vcpkg.json exists. vcpkg add port will create it if it doesn't.
-    //    For a 'rig add' command, it's more robust to ensure we are in a context
-    //    where vcpkg.json is expected.
+    // 3. Ensure vcpkg.json exists. For a 'rig add' command, it's more robust
+    //    to ensure we are in a context where vcpkg.json is expected.
     let vcpkg_json_path = project_root.join(VCPKG_JSON_FILENAME);
     if !vcpkg_json_path.exists() {
-        // While `vcpkg add port` can create vcpkg.json,
-        // for `rig add`, it's better to be explicit that this command
-        // is intended for existing vcpkg manifest projects.
-        // Or, we could choose to initialize it like `vcpkg new --application`
-        // but that might be unexpected for a simple `add`.
-        // Let's require `vcpkg.json` to exist for now.
         bail!(
             "{} not found in project root ({}). Initialize vcpkg manifest first (e.g., via `rig new` or `vcpkg new --application`).",
             VCPKG_JSON_FILENAME,
             project_root.display()
         );
     }
+    let mut manifest = VcpkgManifest::load(&vcpkg_json_path)?;
 
-    // 4. Construct and run the vcpkg command
-    let mut args: Vec<&str> = vec!["add", "port"];
-    let dep_refs: Vec<&str> = dependencies_to_add.iter().map(AsRef::as_ref).collect();
-    args.extend_from_slice(&dep_refs);
+    // 4. Validate everything before the manifest is touched
+    for spec in &specs {
+        verify_against_checkout(vcpkg_paths, spec)?;
+    }
+    if specs.iter().any(|spec| spec.version.is_some()) && vcpkg::current_baseline(project_root, &manifest).is_none() {
+        bail!(
+            "Version constraints need a baseline, but {} has no \"builtin-baseline\" (and {} sets no default-registry baseline). Add one with `vcpkg x-update-baseline --add-initial-baseline`.",
+            VCPKG_JSON_FILENAME,
+            vcpkg::VCPKG_CONFIGURATION_FILENAME
+        );
+    }
 
-    run_command(&vcpkg_paths.exe, &args, Some(&project_root))
-        .with_context(|| format!("Failed to add vcpkg dependencies: {:?}", dependencies_to_add))?;
+    // 5. Write the entries. Qualifiers are merged into existing entries, never replace them.
+    let qualified_flags = no_default_features || platform.is_some();
+    let mut changed = false;
+    for spec in &specs {
+        let already_present = manifest.has_dependency(&spec.port);
+        if already_present && !spec.is_qualified() && !qualified_flags {
+            println!("'{}' is already a dependency in {}.", spec.port, VCPKG_JSON_FILENAME);
+            continue;
+        }
+        let dependency = spec.to_dependency(no_default_features, platform.as_deref());
+        if manifest.merge_dependency(dependency) {
+            println!("Updated '{}' in {}.", spec.port, VCPKG_JSON_FILENAME);
+        } else {
+            println!("Added '{}' to {}.", spec.port, VCPKG_JSON_FILENAME);
+        }
+        changed = true;
+    }
+    if changed {
+        manifest.save(&vcpkg_json_path)?;
+    }
 
-    println!(
-        "Successfully added dependencies: {:?}. Update your CMakeLists.txt if necessary.",
-        dependencies_to_add
-    );
-    println!("You might need to add 'find_package(<dependency> CONFIG REQUIRED)' and link against '<dependency>::<dependency>'.");
+    // 6. Wire the new ports into CMakeLists.txt and the package config template
+    let ports: Vec<String> = specs.into_iter().map(|spec| spec.port).collect();
+    update_cmakelists(project_root, &ports, force)?;
+    update_package_config(config, &ports)?;
 
     Ok(())
-}
\ No newline at end of file
+}
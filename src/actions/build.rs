@@ -1,48 +1,267 @@
 // src/actions/build.rs
 use anyhow::{bail, Context, Result};
+use serde_json::json;
 use std::fs;
-use std::path::Path;
-use std::process::Command as OsCommand;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command as OsCommand, ExitStatus, Stdio};
+use std::sync::mpsc;
+use std::thread;
+use std::time::SystemTime;
 
-use crate::cmake::CMAKELISTS_FILENAME;
-use crate::utils::find_project_root_by_marker; // Renamed to avoid conflict
+use crate::config::ProjectConfig;
+use crate::diagnostics::{Diagnostic, DiagnosticParser, DiagnosticSummary, MessageFormat};
+use crate::fileapi;
+use crate::fingerprint::Fingerprint;
+use crate::presets::{effective_preset_lines, EffectiveConfigurePreset, Presets};
+use crate::sarif::{self, ColumnUnit};
+use crate::utils;
+
+// Prints a human-readable line. In JSON mode stdout carries only JSON objects,
+// so these go to stderr.
+fn report(format: MessageFormat, message: impl std::fmt::Display) {
+    match format {
+        MessageFormat::Human => println!("{}", message),
+        MessageFormat::Json => eprintln!("{}", message),
+    }
+}
 
 // Helper to check if CMake configuration is needed
-fn is_cmake_configured(project_root: &Path, preset_name: &str) -> Result<bool> {
+fn is_cmake_configured(build_dir: &Path) -> bool {
     // A simple check: if CMakeCache.txt exists in the build directory for the preset.
     // This isn't foolproof but is a common indicator.
-    let build_dir = project_root.join("build").join(preset_name);
-    Ok(build_dir.join("CMakeCache.txt").exists())
+    build_dir.join("CMakeCache.txt").exists()
 }
 
+/// When the CMake configure step runs for an already configured build tree.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Reconfigure {
+    /// Only when one of the configure inputs changed since the last configure.
+    #[default]
+    IfChanged,
+    /// Always (`--reconfigure`).
+    Always,
+    /// Always, discarding the CMake cache first (`--fresh`).
+    Fresh,
+}
 
-pub fn build_project(preset_name: &str, clean_build: bool) -> Result<()> {
-    let project_root = find_project_root_by_marker(CMAKELISTS_FILENAME)
-        .context("Failed to find project root (CMakeLists.txt). Are you in a CMake project?")?;
+/// Options of the `cmake --build` step.
+#[derive(Default)]
+pub struct BuildOptions {
+    /// Targets to build; everything when empty.
+    pub targets: Vec<String>,
+    pub jobs: Option<u32>,
+    /// Keep building other targets after a failure.
+    pub keep_going: bool,
+    pub verbose: bool,
+    /// Arguments passed to the native build tool after `--`.
+    pub native_args: Vec<String>,
+    pub message_format: MessageFormat,
+    /// Where to write the diagnostics as a SARIF log.
+    pub sarif: Option<PathBuf>,
+}
 
-    println!(
-        "Building project at '{}' using preset '{}'...",
-        project_root.display(),
-        preset_name
-    );
+/// The value of `name` in the build directory's CMakeCache.txt, whatever its type.
+pub fn cached_variable(build_dir: &Path, name: &str) -> Option<String> {
+    let cache = fs::read_to_string(build_dir.join("CMakeCache.txt")).ok()?;
+    // Entries look like `NAME:TYPE=value`.
+    cache.lines().find_map(|line| {
+        let (key, value) = line.split_once('=')?;
+        let (key_name, _) = key.split_once(':')?;
+        (key_name == name).then(|| value.to_string())
+    })
+}
+
+// How the build's C++ compiler counts columns, from the compiler identification
+// CMake writes to `CMakeFiles/<version>/CMakeCXXCompiler.cmake`.
+fn column_unit(build_dir: &Path) -> ColumnUnit {
+    let setting = |content: &str, name: &str| {
+        content.lines().find_map(|line| {
+            let value = line.trim().strip_prefix(&format!("set({} ", name))?;
+            Some(value.strip_suffix(')')?.trim_matches('"').to_string())
+        })
+    };
+    let identification = fs::read_dir(build_dir.join("CMakeFiles"))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .find_map(|entry| fs::read_to_string(entry.path().join("CMakeCXXCompiler.cmake")).ok());
+    identification
+        .and_then(|content| {
+            Some(ColumnUnit::for_compiler(
+                &setting(&content, "CMAKE_CXX_COMPILER_ID")?,
+                &setting(&content, "CMAKE_CXX_COMPILER_VERSION")?,
+            ))
+        })
+        .unwrap_or(ColumnUnit::Byte)
+}
+
+// The native build tool's "keep going" flags; CMake has no portable option for it.
+fn keep_going_args(generator: &str) -> Option<&'static [&'static str]> {
+    if generator.starts_with("Ninja") {
+        Some(&["-k", "0"])
+    } else if generator == "NMake Makefiles" {
+        Some(&["/K"])
+    } else if generator.ends_with("Makefiles") {
+        Some(&["-k"])
+    } else {
+        None
+    }
+}
+
+// Sends each line read from `stream` to `sender`, tagged with whether it came from stderr.
+fn forward_lines(
+    stream: impl Read + Send + 'static,
+    is_stderr: bool,
+    sender: mpsc::Sender<(bool, String)>,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let mut reader = BufReader::new(stream);
+        let mut buffer = Vec::new();
+        // Compiler output is not always valid UTF-8, so lines are read as bytes.
+        while reader.read_until(b'\n', &mut buffer).is_ok_and(|n| n > 0) {
+            let line = String::from_utf8_lossy(&buffer);
+            let line = line.trim_end_matches(['\n', '\r']).to_string();
+            if sender.send((is_stderr, line)).is_err() {
+                break;
+            }
+            buffer.clear();
+        }
+    })
+}
+
+// Runs the build with its output captured and parsed into diagnostics. In human
+// mode the output is echoed as it arrives; in JSON mode it goes to stderr and
+// each diagnostic is printed to stdout as a JSON object. Repeated diagnostics
+// (a header warning seen from several sources) are reported once.
+fn run_build_command(
+    command: &mut OsCommand,
+    format: MessageFormat,
+) -> Result<(ExitStatus, Vec<Diagnostic>)> {
+    let mut child = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to execute cmake build command.")?;
 
-    let build_dir = project_root.join("build").join(preset_name);
+    let (sender, receiver) = mpsc::channel();
+    let mut readers = Vec::new();
+    if let Some(stdout) = child.stdout.take() {
+        readers.push(forward_lines(stdout, false, sender.clone()));
+    }
+    if let Some(stderr) = child.stderr.take() {
+        readers.push(forward_lines(stderr, true, sender.clone()));
+    }
+    drop(sender);
+
+    let mut parser = DiagnosticParser::default();
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+    let mut report = |diagnostic: Diagnostic| {
+        if diagnostics.iter().any(|d| d.same_as(&diagnostic)) {
+            return;
+        }
+        if format == MessageFormat::Json {
+            println!("{}", json!({ "reason": "compiler-message", "diagnostic": diagnostic }));
+        }
+        diagnostics.push(diagnostic);
+    };
+    for (is_stderr, line) in receiver {
+        if is_stderr || format == MessageFormat::Json {
+            eprintln!("{}", line);
+        } else {
+            println!("{}", line);
+        }
+        if let Some(diagnostic) = parser.feed(&line) {
+            report(diagnostic);
+        }
+    }
+    if let Some(diagnostic) = parser.finish() {
+        report(diagnostic);
+    }
+    for reader in readers {
+        let _ = reader.join();
+    }
+    let _ = io::stdout().flush();
+
+    let status = child.wait().context("Failed to wait for the cmake build command.")?;
+    Ok((status, diagnostics))
+}
+
+/// Configures the build tree of the project's preset if needed and returns the
+/// resolved preset. `clean_build` removes the build directory first. With
+/// `MessageFormat::Json` all output, CMake's included, goes to stderr.
+pub fn configure_project(
+    config: &ProjectConfig,
+    clean_build: bool,
+    reconfigure: Reconfigure,
+    format: MessageFormat,
+) -> Result<EffectiveConfigurePreset> {
+    let project_root = &config.project_path;
+    let preset_name = config.preset.as_str();
+
+    let presets = Presets::load(project_root)?;
+    let preset = presets.configure_preset(preset_name)?;
+    let build_dir = preset.binary_dir.clone();
 
     if clean_build && build_dir.exists() {
-        println!("Cleaning build directory: {:?}", build_dir);
-        fs::remove_dir_all(&build_dir)
+        report(format, format_args!("Cleaning build directory: {:?}", build_dir));
+        utils::remove_dir_all(&build_dir)
             .with_context(|| format!("Failed to clean build directory: {:?}", build_dir))?;
     }
 
-    // 1. Configure step (if not already configured, or if clean_build)
-    //    CMake presets handle idempotency well, so re-configuring is usually fine.
-    //    However, explicitly checking can save a bit of time if not needed.
-    if clean_build || !is_cmake_configured(&project_root, preset_name)? {
-        println!("Configuring CMake with preset '{}'...", preset_name);
-        let configure_status = OsCommand::new("cmake")
-            .arg("--preset")
-            .arg(preset_name)
-            .current_dir(&project_root)
+    // The File API query has to exist before configuring for CMake to answer it
+    // (`rig run` reads the reply), so build trees without one are reconfigured.
+    let missing_query = !fileapi::has_codemodel_query(&build_dir);
+    if missing_query {
+        fileapi::write_codemodel_query(&build_dir)?;
+    }
+
+    // CMake re-runs itself when CMakeLists.txt changes; everything else that
+    // feeds the configure step is covered by the fingerprint.
+    let fingerprint = Fingerprint::compute(config, &presets, &preset);
+    let reason = if clean_build {
+        Some("clean build".to_string())
+    } else if reconfigure == Reconfigure::Fresh {
+        Some("--fresh".to_string())
+    } else if reconfigure == Reconfigure::Always {
+        Some("--reconfigure".to_string())
+    } else if !is_cmake_configured(&build_dir) {
+        Some("not configured yet".to_string())
+    } else if missing_query {
+        Some("File API query added".to_string())
+    } else {
+        match Fingerprint::read(&build_dir) {
+            None => Some("no configure fingerprint".to_string()),
+            Some(previous) => {
+                let changed = fingerprint.changed_inputs(&previous);
+                (!changed.is_empty()).then(|| format!("changed: {}", changed.join(", ")))
+            }
+        }
+    };
+
+    if let Some(reason) = reason {
+        report(
+            format,
+            format_args!("Configuring CMake with preset '{}' ({})...", preset_name, reason),
+        );
+        for line in effective_preset_lines(&preset) {
+            report(format, line);
+        }
+        // A failed configure must not leave the old fingerprint behind.
+        Fingerprint::remove(&build_dir)?;
+        let mut command = OsCommand::new("cmake");
+        command.arg("--preset").arg(preset_name);
+        if reconfigure == Reconfigure::Fresh {
+            command.arg("--fresh");
+        }
+        command.current_dir(project_root);
+        if format == MessageFormat::Json {
+            command.stdout(io::stderr());
+        }
+        if utils::skip_in_dry_run(&command) {
+            return Ok(preset);
+        }
+        let configure_status = command
             .status()
             .context("Failed to execute cmake configure command.")?;
 
@@ -53,26 +272,119 @@ pub fn build_project(preset_name: &str, clean_build: bool) -> Result<()> {
                 configure_status.code()
             );
         }
-        println!("CMake configuration successful.");
+        fingerprint.write(&build_dir)?;
+        report(format, "CMake configuration successful.");
     } else {
-        println!("CMake already configured for preset '{}'. Skipping configuration.", preset_name);
-    }
-
-
-    // 2. Build step
-    println!("Building with CMake using preset '{}'...", preset_name);
-    let build_status = OsCommand::new("cmake")
-        .arg("--build")
-        // .arg("--preset") // Note: Some older CMake versions might prefer build_dir path directly
-        // .arg(preset_name) // For newer CMake: cmake --build --preset <name>
-        .arg(build_dir) // More compatible: cmake --build <build_dir>
-        // If you want to pass specific build args like -j
-        // .arg("--")
-        // .arg("-j")
-        // .arg(num_cpus::get().to_string()) // Example: use all cores
-        .current_dir(&project_root) // Not strictly necessary if using build_dir, but good practice
-        .status()
-        .context("Failed to execute cmake build command.")?;
+        report(
+            format,
+            format_args!(
+                "CMake already configured for preset '{}' and its inputs are unchanged. Skipping configuration.",
+                preset_name
+            ),
+        );
+    }
+    Ok(preset)
+}
+
+/// Runs `cmake --build` on the configured build tree of `preset`.
+pub fn build_configured(
+    config: &ProjectConfig,
+    preset: &EffectiveConfigurePreset,
+    options: &BuildOptions,
+) -> Result<()> {
+    let preset_name = config.preset.as_str();
+    let build_dir = &preset.binary_dir;
+
+    let mut command = OsCommand::new("cmake");
+    // `cmake --build <build_dir>` works with CMake versions that predate build presets.
+    command.arg("--build").arg(build_dir);
+    if !options.targets.is_empty() {
+        command.arg("--target").args(&options.targets);
+    }
+    if let Some(jobs) = options.jobs {
+        command.arg("--parallel").arg(jobs.to_string());
+    }
+    if options.verbose {
+        command.arg("--verbose");
+    }
+
+    let mut native_args: Vec<String> = Vec::new();
+    if options.keep_going {
+        // The generator the build tree was configured with, else the preset's.
+        let generator = cached_variable(build_dir, "CMAKE_GENERATOR")
+            .or_else(|| preset.generator.clone());
+        match generator.as_deref().and_then(keep_going_args) {
+            Some(args) => native_args.extend(args.iter().map(|a| a.to_string())),
+            None => report(
+                options.message_format,
+                format_args!(
+                    "Note: --keep-going is not supported for the '{}' generator; ignoring it.",
+                    generator.as_deref().unwrap_or("default")
+                ),
+            ),
+        }
+    }
+    native_args.extend(options.native_args.iter().cloned());
+    if !native_args.is_empty() {
+        command.arg("--").args(&native_args);
+    }
+    command.current_dir(&config.project_path);
+
+    if options.targets.is_empty() {
+        report(
+            options.message_format,
+            format_args!("Building with CMake using preset '{}'...", preset_name),
+        );
+    } else {
+        report(
+            options.message_format,
+            format_args!(
+                "Building target(s) {} with CMake using preset '{}'...",
+                options.targets.join(", "),
+                preset_name
+            ),
+        );
+    }
+    if utils::skip_in_dry_run(&command) {
+        return Ok(());
+    }
+    let build_started = SystemTime::now();
+    let (build_status, diagnostics) = run_build_command(&mut command, options.message_format)?;
+
+    // Written before a failed build bails out: that is when the log matters most.
+    if let Some(sarif_path) = &options.sarif {
+        sarif::write_log(
+            sarif_path,
+            &config.project_path,
+            build_dir,
+            "rig",
+            column_unit(build_dir),
+            &diagnostics,
+            sarif::gcc_sarif_runs(build_dir, build_started),
+        )?;
+        report(
+            options.message_format,
+            format_args!("Wrote SARIF log to {}", sarif_path.display()),
+        );
+    }
+
+    let mut summary = DiagnosticSummary::default();
+    for diagnostic in &diagnostics {
+        summary.record(diagnostic);
+    }
+    match options.message_format {
+        MessageFormat::Human => println!("{}", summary),
+        MessageFormat::Json => println!(
+            "{}",
+            json!({
+                "reason": "build-finished",
+                "success": build_status.success(),
+                "errors": summary.errors,
+                "warnings": summary.warnings,
+                "files": summary.file_count(),
+            })
+        ),
+    }
 
     if !build_status.success() {
         bail!(
@@ -82,6 +394,27 @@ pub fn build_project(preset_name: &str, clean_build: bool) -> Result<()> {
         );
     }
 
-    println!("Build successful for preset '{}'.", preset_name);
+    report(
+        options.message_format,
+        format_args!("Build successful for preset '{}'.", preset_name),
+    );
     Ok(())
-}
\ No newline at end of file
+}
+
+pub fn build_project(
+    config: &ProjectConfig,
+    clean_build: bool,
+    reconfigure: Reconfigure,
+    options: &BuildOptions,
+) -> Result<()> {
+    report(
+        options.message_format,
+        format_args!(
+            "Building project at '{}' using preset '{}'...",
+            config.project_path.display(),
+            config.preset
+        ),
+    );
+    let preset = configure_project(config, clean_build, reconfigure, options.message_format)?;
+    build_configured(config, &preset, options)
+}
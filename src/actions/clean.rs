@@ -1,40 +1,78 @@
 // src/actions/clean.rs
-use anyhow::{Context, Result, bail};
-use std::fs;
-use crate::utils::find_project_root_by_marker;
-use crate::cmake::CMAKELISTS_FILENAME; // Using CMakeLists.txt as a common project marker
+use anyhow::{bail, Context, Result};
+use std::path::Path;
+use crate::config::ProjectConfig;
+use crate::presets::Presets;
+use crate::utils;
 
-pub fn clean_project(preset: Option<String>, all: bool) -> Result<()> {
-    let project_root = find_project_root_by_marker(CMAKELISTS_FILENAME)
-        .context("Failed to find project root (CMakeLists.txt). Are you in a CMake project?")?;
-
-    let build_dir_base = project_root.join("build");
+// Removes one preset's binary directory. The source tree itself (in-source
+// builds) is never removed.
+fn remove_build_dir(project_root: &Path, preset_name: &str, build_dir: &Path) -> Result<()> {
+    if project_root.starts_with(build_dir) {
+        bail!(
+            "Refusing to clean preset '{}': its binaryDir '{}' contains the source directory.",
+            preset_name,
+            build_dir.display()
+        );
+    }
+    if build_dir.exists() {
+        println!("Cleaning build artifacts for preset '{}' in '{}'...", preset_name, build_dir.display());
+        utils::remove_dir_all(build_dir)
+            .with_context(|| format!("Failed to remove directory: {:?}", build_dir))?;
+        println!("Successfully cleaned build artifacts for preset '{}'.", preset_name);
+    } else {
+        println!("Build directory for preset '{}' ('{}') does not exist. Nothing to clean.", preset_name, build_dir.display());
+    }
+    Ok(())
+}
 
-    if !build_dir_base.exists() || !build_dir_base.is_dir() {
-        println!("Build directory '{}' does not exist or is not a directory. Nothing to clean.", build_dir_base.display());
+// Removes one preset's install directory, if it has one.
+fn remove_install_dir(presets: &Presets, project_root: &Path, preset_name: &str) -> Result<()> {
+    let Some(install_dir) = presets.configure_preset(preset_name)?.install_dir else {
+        println!("Preset '{}' has no installDir. Nothing to uninstall.", preset_name);
         return Ok(());
+    };
+    if project_root.starts_with(&install_dir) {
+        bail!(
+            "Refusing to clean preset '{}': its installDir '{}' contains the source directory.",
+            preset_name,
+            install_dir.display()
+        );
+    }
+    if install_dir.exists() {
+        println!("Removing installed files for preset '{}' in '{}'...", preset_name, install_dir.display());
+        utils::remove_dir_all(&install_dir)
+            .with_context(|| format!("Failed to remove directory: {:?}", install_dir))?;
+        println!("Successfully removed the install tree of preset '{}'.", preset_name);
+    } else {
+        println!("Install directory for preset '{}' ('{}') does not exist. Nothing to clean.", preset_name, install_dir.display());
     }
+    Ok(())
+}
+
+// Without --all, the preset comes from --preset or rig.toml (see `ProjectConfig::load`).
+// With `install`, the presets' install trees are removed as well.
+pub fn clean_project(config: &ProjectConfig, all: bool, install: bool) -> Result<()> {
+    let project_root = &config.project_path;
+    let presets = Presets::load(project_root)?;
 
     if all {
-        println!("Cleaning all build artifacts in '{}'...", build_dir_base.display());
-        fs::remove_dir_all(&build_dir_base)
-            .with_context(|| format!("Failed to remove directory: {:?}", build_dir_base))?;
-        println!("Successfully cleaned all build artifacts.");
-    } else if let Some(p_name) = preset {
-        let preset_build_dir = build_dir_base.join(&p_name);
-        if preset_build_dir.exists() {
-            println!("Cleaning build artifacts for preset '{}' in '{}'...", p_name, preset_build_dir.display());
-            fs::remove_dir_all(&preset_build_dir)
-                .with_context(|| format!("Failed to remove directory: {:?}", preset_build_dir))?;
-            println!("Successfully cleaned build artifacts for preset '{}'.", p_name);
-        } else {
-            println!("Build directory for preset '{}' ('{}') does not exist. Nothing to clean.", p_name, preset_build_dir.display());
+        println!("Cleaning the build directories of all presets...");
+        for preset in presets.visible_configure_presets() {
+            let build_dir = presets.binary_dir(&preset.name)?;
+            remove_build_dir(project_root, &preset.name, &build_dir)?;
+            if install {
+                remove_install_dir(&presets, project_root, &preset.name)?;
+            }
         }
+        println!("Successfully cleaned all build artifacts.");
     } else {
-        // This case should ideally not be reached if clap group is required.
-        // If it can be reached, we might want to default to cleaning 'dev' or error.
-        bail!("Please specify a preset to clean or use the --all flag. e.g., `rig clean --preset dev` or `rig clean --all`");
+        let build_dir = presets.binary_dir(&config.preset)?;
+        remove_build_dir(project_root, &config.preset, &build_dir)?;
+        if install {
+            remove_install_dir(&presets, project_root, &config.preset)?;
+        }
     }
 
     Ok(())
-}
\ No newline at end of file
+}
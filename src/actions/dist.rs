@@ -0,0 +1,378 @@
+// src/actions/dist.rs
+use crate::actions::build::cached_variable;
+use crate::actions::install::{install_project, InstallOptions};
+use crate::config::ProjectConfig;
+use crate::elf;
+use crate::fingerprint::sha256_hex;
+use crate::manifest::VcpkgManifest;
+use crate::presets::{EffectiveConfigurePreset, Presets};
+use crate::toolchain;
+use crate::utils::{self, run_command_capture};
+use crate::vcpkg::{self, InstalledPort, VCPKG_JSON_FILENAME};
+use anyhow::{bail, Context, Result};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde_json::json;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::env;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+const DIST_DIRNAME: &str = "dist";
+const STAGING_DIRNAME: &str = "rig-dist";
+const DIST_MANIFEST_FILENAME: &str = "rig-dist-manifest.json";
+// Where vcpkg installs shared libraries, relative to a triplet directory.
+const VCPKG_LIB_DIRNAME: &str = "lib";
+
+/// Options of `rig dist`.
+pub struct DistOptions {
+    /// Directory for the archive and its checksum, instead of `dist/`
+    pub output_dir: Option<PathBuf>,
+}
+
+// A shared library copied into the package from vcpkg_installed.
+struct BundledLibrary {
+    source: PathBuf,
+    port: Option<InstalledPort>,
+}
+
+// Every file and symlink under `dir`, relative to it and sorted. Symlinks are not followed.
+fn package_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        let entries = fs::read_dir(&current)
+            .with_context(|| format!("Failed to read directory: {:?}", current))?;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if entry.file_type().is_ok_and(|t| t.is_dir()) {
+                pending.push(path);
+            } else if let Ok(relative) = path.strip_prefix(dir) {
+                files.push(relative.to_path_buf());
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+// The regular ELF files of the package, as absolute paths.
+fn elf_files(stage: &Path) -> Result<Vec<PathBuf>> {
+    Ok(package_files(stage)?
+        .into_iter()
+        .map(|relative| stage.join(relative))
+        .filter(|path| {
+            fs::symlink_metadata(path).is_ok_and(|m| m.is_file()) && elf::is_elf(path)
+        })
+        .collect())
+}
+
+// The package's library directory, relative to its root: where the install
+// step puts the project's own libraries (CMAKE_INSTALL_LIBDIR, e.g. `lib64` or
+// `lib/x86_64-linux-gnu`), and where vcpkg libraries are bundled.
+fn package_lib_dir(build_dir: &Path) -> PathBuf {
+    cached_variable(build_dir, "CMAKE_INSTALL_LIBDIR")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_relative() && !dir.as_os_str().is_empty())
+        .unwrap_or_else(|| PathBuf::from(VCPKG_LIB_DIRNAME))
+}
+
+// The vcpkg triplet of the build tree: from CMakeCache.txt, else the preset,
+// else the only triplet installed.
+fn target_triplet(
+    build_dir: &Path,
+    preset: &EffectiveConfigurePreset,
+    installed_root: &Path,
+) -> Option<String> {
+    if let Some(triplet) = cached_variable(build_dir, "VCPKG_TARGET_TRIPLET")
+        .or_else(|| preset.cache_variables.get("VCPKG_TARGET_TRIPLET").cloned())
+    {
+        return Some(triplet);
+    }
+    let triplets: Vec<String> = fs::read_dir(installed_root)
+        .ok()?
+        .flatten()
+        .filter(|entry| entry.file_name() != "vcpkg" && entry.path().is_dir())
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .collect();
+    match triplets.as_slice() {
+        [triplet] => Some(triplet.clone()),
+        _ => None,
+    }
+}
+
+// Copies the libraries from `vcpkg_lib_dir` that the package's ELF files need,
+// transitively, into its library directory. Returns the bundled libraries and
+// the needed ones left to the target system, such as libc.so.6.
+fn bundle_libraries(
+    stage: &Path,
+    lib_dirname: &Path,
+    vcpkg_lib_dir: Option<&Path>,
+    triplet: Option<&str>,
+    owners: &HashMap<String, InstalledPort>,
+) -> Result<(BTreeMap<String, BundledLibrary>, BTreeSet<String>)> {
+    let lib_dir = stage.join(lib_dirname);
+    let mut bundled = BTreeMap::new();
+    let mut system = BTreeSet::new();
+    let mut pending = elf_files(stage)?;
+    while let Some(file) = pending.pop() {
+        let Some(needed) = elf::needed_libraries(&file)? else {
+            continue;
+        };
+        for name in needed {
+            // The project's own shared libraries are installed already.
+            if bundled.contains_key(&name) || system.contains(&name) || lib_dir.join(&name).exists() {
+                continue;
+            }
+            let Some(source) = vcpkg_lib_dir
+                .map(|dir| dir.join(&name))
+                .filter(|path| path.exists())
+            else {
+                system.insert(name);
+                continue;
+            };
+            utils::create_dir_all(&lib_dir)
+                .with_context(|| format!("Failed to create directory: {:?}", lib_dir))?;
+            // The soname is usually a symlink; the library it points to is copied under that name.
+            let target = lib_dir.join(&name);
+            utils::copy(&source, &target)
+                .with_context(|| format!("Failed to copy {:?} to {:?}", source, target))?;
+            let port = triplet
+                .and_then(|triplet| {
+                    owners.get(&format!("{}/{}/{}", triplet, VCPKG_LIB_DIRNAME, name))
+                })
+                .cloned();
+            pending.push(target);
+            bundled.insert(name, BundledLibrary { source, port });
+        }
+    }
+    Ok((bundled, system))
+}
+
+// `$ORIGIN`-relative run path from the directory of `file` to the package's
+// library directory.
+fn origin_rpath(stage: &Path, lib_dirname: &Path, file: &Path) -> String {
+    let depth = file
+        .parent()
+        .and_then(|dir| dir.strip_prefix(stage).ok())
+        .map_or(0, |relative| relative.components().count());
+    let lib_dirname = lib_dirname
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+    format!("$ORIGIN{}/{}", "/..".repeat(depth), lib_dirname)
+}
+
+fn set_rpaths(patchelf: &Path, stage: &Path, lib_dirname: &Path) -> Result<usize> {
+    let mut patched = 0;
+    for file in elf_files(stage)? {
+        // Statically linked files and files without dependencies need no run path.
+        if elf::needed_libraries(&file)?.is_none_or(|needed| needed.is_empty()) {
+            continue;
+        }
+        let rpath = origin_rpath(stage, lib_dirname, &file);
+        let file_arg = file.to_string_lossy();
+        run_command_capture(patchelf, &["--set-rpath", &rpath, &file_arg], None)
+            .with_context(|| format!("Failed to set the RPATH of {:?}", file))?;
+        patched += 1;
+    }
+    Ok(patched)
+}
+
+fn write_dist_manifest(
+    stage: &Path,
+    lib_dirname: &Path,
+    config: &ProjectConfig,
+    version: Option<&str>,
+    triplet: Option<&str>,
+    bundled: &BTreeMap<String, BundledLibrary>,
+    system: &BTreeSet<String>,
+) -> Result<()> {
+    let mut files = Vec::new();
+    for relative in package_files(stage)? {
+        let path = stage.join(&relative);
+        let shown = relative.to_string_lossy().replace('\\', "/");
+        let metadata = fs::symlink_metadata(&path)
+            .with_context(|| format!("Failed to read metadata of {:?}", path))?;
+        if metadata.is_symlink() {
+            let target = fs::read_link(&path)
+                .with_context(|| format!("Failed to read symlink {:?}", path))?;
+            files.push(json!({ "path": shown, "symlink": target.to_string_lossy() }));
+            continue;
+        }
+        let content = fs::read(&path).with_context(|| format!("Failed to read {:?}", path))?;
+        let library = relative
+            .strip_prefix(lib_dirname)
+            .ok()
+            .and_then(|name| bundled.get(name.to_string_lossy().as_ref()));
+        let origin = match library {
+            Some(library) => json!({
+                "vcpkg": library.port.as_ref().map(|port| json!({
+                    "port": port.name,
+                    "version": port.version,
+                })),
+            }),
+            None => json!("project"),
+        };
+        files.push(json!({
+            "path": shown,
+            "sha256": sha256_hex(&content),
+            "origin": origin,
+        }));
+    }
+
+    let manifest = json!({
+        "name": config.project_name,
+        "version": version,
+        "preset": config.preset,
+        "triplet": triplet,
+        "files": files,
+        "systemLibraries": system,
+    });
+    let path = stage.join(DIST_MANIFEST_FILENAME);
+    let content = serde_json::to_string_pretty(&manifest)
+        .context("Failed to serialize the dist manifest")?;
+    utils::write(&path, content).with_context(|| format!("Failed to write {:?}", path))
+}
+
+fn write_archive(stage: &Path, root_name: &str, archive: &Path) -> Result<()> {
+    let file = File::create(archive)
+        .with_context(|| format!("Failed to create archive {:?}", archive))?;
+    let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+    // Keep the symlinks CMake installs next to versioned shared libraries.
+    builder.follow_symlinks(false);
+    builder
+        .append_dir_all(root_name, stage)
+        .and_then(|_| builder.into_inner())
+        .and_then(|encoder| encoder.finish())
+        .with_context(|| format!("Failed to write archive {:?}", archive))?;
+    Ok(())
+}
+
+// Writes `<archive>.sha256` in `sha256sum` format and returns the hash.
+fn write_checksum(archive: &Path) -> Result<(String, PathBuf)> {
+    let content = fs::read(archive).with_context(|| format!("Failed to read {:?}", archive))?;
+    let hash = sha256_hex(&content);
+    let file_name = archive.file_name().unwrap_or_default().to_string_lossy();
+    let path = archive.with_file_name(format!("{}.sha256", file_name));
+    utils::write(&path, format!("{}  {}\n", hash, file_name))
+        .with_context(|| format!("Failed to write {:?}", path))?;
+    Ok((hash, path))
+}
+
+pub fn dist_project(config: &ProjectConfig, options: &DistOptions) -> Result<()> {
+    if env::consts::OS != "linux" {
+        bail!("`rig dist` packages ELF binaries and only works on Linux.");
+    }
+    let Some(patchelf) = toolchain::find_program("patchelf") else {
+        bail!("patchelf was not found on PATH. Install it (e.g. `apt install patchelf`) to use `rig dist`.");
+    };
+    let project_root = &config.project_path;
+    let preset = Presets::load(project_root)?.configure_preset(&config.preset)?;
+    let build_dir = &preset.binary_dir;
+
+    let version = VcpkgManifest::load(&project_root.join(VCPKG_JSON_FILENAME))
+        .ok()
+        .and_then(|manifest| manifest.version_text().map(str::to_string));
+    let package_name = match &version {
+        Some(version) => format!("{}-{}-linux-{}", config.project_name, version, env::consts::ARCH),
+        None => format!("{}-linux-{}", config.project_name, env::consts::ARCH),
+    };
+    let output_dir = match &options.output_dir {
+        Some(dir) => dir.clone(),
+        None => project_root.join(DIST_DIRNAME),
+    };
+    let archive = output_dir.join(format!("{}.tar.gz", package_name));
+
+    // Stage a fresh install in the build directory.
+    let stage = build_dir.join(STAGING_DIRNAME).join(&package_name);
+    if stage.exists() {
+        utils::remove_dir_all(&stage)
+            .with_context(|| format!("Failed to remove directory: {:?}", stage))?;
+    }
+    let install_options = InstallOptions {
+        prefix: Some(stage.clone()),
+        ..InstallOptions::default()
+    };
+    install_project(config, &install_options)?;
+    // Which libraries to bundle depends on the staged files, which do not exist.
+    if utils::is_dry_run() {
+        eprintln!(
+            "[dry-run] Would bundle the vcpkg libraries the staged files need, set their RPATH with {} and write {} with its checksum.",
+            patchelf.display(),
+            archive.display()
+        );
+        return Ok(());
+    }
+
+    let installed_root = vcpkg::preset_installed_root(project_root, &preset);
+    let triplet = target_triplet(build_dir, &preset, &installed_root);
+    let vcpkg_lib_dir = triplet.as_ref().map(|t| installed_root.join(t).join(VCPKG_LIB_DIRNAME));
+    let lib_dirname = package_lib_dir(build_dir);
+    match &vcpkg_lib_dir {
+        Some(dir) => println!("Collecting shared libraries from {}...", dir.display()),
+        None => println!(
+            "Note: no vcpkg triplet found for preset '{}'; no libraries are bundled.",
+            config.preset
+        ),
+    }
+    let owners = vcpkg::installed_file_owners(&installed_root);
+    let (bundled, system) =
+        bundle_libraries(&stage, &lib_dirname, vcpkg_lib_dir.as_deref(), triplet.as_deref(), &owners)?;
+    let patched = set_rpaths(&patchelf, &stage, &lib_dirname)?;
+    println!("Set the RPATH of {} files relative to $ORIGIN.", patched);
+    write_dist_manifest(
+        &stage,
+        &lib_dirname,
+        config,
+        version.as_deref(),
+        triplet.as_deref(),
+        &bundled,
+        &system,
+    )?;
+
+    utils::create_dir_all(&output_dir)
+        .with_context(|| format!("Failed to create directory: {:?}", output_dir))?;
+    write_archive(&stage, &package_name, &archive)?;
+    let (hash, checksum) = write_checksum(&archive)?;
+
+    if bundled.is_empty() {
+        println!("No vcpkg libraries bundled.");
+    } else {
+        println!("Bundled {} libraries:", bundled.len());
+        for (name, library) in &bundled {
+            let origin = match &library.port {
+                Some(port) => format!("{} {}", port.name, port.version),
+                None => library.source.display().to_string(),
+            };
+            println!("  {} ({})", lib_dirname.join(name).display(), origin);
+        }
+    }
+    if !system.is_empty() {
+        println!(
+            "Expected on the target system: {}",
+            system.iter().cloned().collect::<Vec<_>>().join(", ")
+        );
+    }
+    println!("Created {}", archive.display());
+    println!("SHA-256 {} ({})", hash, checksum.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rpath_points_at_package_lib_dir() {
+        let stage = Path::new("/stage");
+        let rpath = |lib_dirname: &str, file: &str| {
+            origin_rpath(stage, Path::new(lib_dirname), &stage.join(file))
+        };
+        assert_eq!(rpath("lib", "bin/app"), "$ORIGIN/../lib");
+        assert_eq!(rpath("lib64", "bin/app"), "$ORIGIN/../lib64");
+        assert_eq!(rpath("lib64", "lib64/libapp.so"), "$ORIGIN/../lib64");
+        assert_eq!(rpath("lib/x86_64-linux-gnu", "libexec/app/tool"), "$ORIGIN/../../lib/x86_64-linux-gnu");
+    }
+}
@@ -0,0 +1,143 @@
+// src/actions/fmt.rs
+use crate::config::{ProjectConfig, CLANG_FORMAT_FILENAME};
+use crate::toolchain;
+use crate::utils::{self, list_source_files, run_parallel, unified_diff};
+use anyhow::{bail, Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command as OsCommand;
+
+/// The `.clang-format` written by `rig new --style`.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FormatStyle {
+    #[default]
+    Llvm,
+    Google,
+    Mozilla,
+    /// LLVM with 4-space indentation and a 100-column limit
+    Custom,
+}
+
+pub(crate) fn generate_clang_format_content(style: FormatStyle) -> String {
+    let header = "# Formatting for `rig fmt`; see https://clang.llvm.org/docs/ClangFormatStyleOptions.html\n";
+    let body = match style {
+        FormatStyle::Llvm => "BasedOnStyle: LLVM\n",
+        FormatStyle::Google => "BasedOnStyle: Google\n",
+        FormatStyle::Mozilla => "BasedOnStyle: Mozilla\n",
+        FormatStyle::Custom => {
+            r#"BasedOnStyle: LLVM
+IndentWidth: 4
+ColumnLimit: 100
+AccessModifierOffset: -4
+AllowShortFunctionsOnASingleLine: Inline
+AllowShortIfStatementsOnASingleLine: Never
+BreakBeforeBraces: Attach
+PointerAlignment: Left
+IncludeBlocks: Regroup
+SortIncludes: CaseSensitive
+"#
+        }
+    };
+    format!("{}{}", header, body)
+}
+
+// A file whose formatted contents differ from what is on disk.
+struct Unformatted {
+    path: PathBuf,
+    original: String,
+    formatted: String,
+}
+
+fn format_file(clang_format: &Path, project_root: &Path, path: &Path) -> Result<Option<Unformatted>> {
+    let original =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {:?}", path))?;
+    // `--style=file` picks up the nearest .clang-format, falling back to LLVM.
+    let output = OsCommand::new(clang_format)
+        .arg("--style=file")
+        .arg(path)
+        .current_dir(project_root)
+        .output()
+        .with_context(|| format!("Failed to execute {}", clang_format.display()))?;
+    if !output.status.success() {
+        bail!(
+            "clang-format failed on {:?}: {}",
+            path,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    let formatted = String::from_utf8(output.stdout)
+        .with_context(|| format!("clang-format produced invalid UTF-8 for {:?}", path))?;
+    Ok((formatted != original).then(|| Unformatted {
+        path: path.to_path_buf(),
+        original,
+        formatted,
+    }))
+}
+
+// Runs clang-format over `files` on one thread per CPU.
+fn format_files(clang_format: &Path, project_root: &Path, files: &[PathBuf]) -> Result<Vec<Unformatted>> {
+    let results = run_parallel(files, usize::MAX, |path| {
+        format_file(clang_format, project_root, path)
+    });
+    let mut unformatted = Vec::new();
+    for result in results {
+        if let Some(file) = result? {
+            unformatted.push(file);
+        }
+    }
+    Ok(unformatted)
+}
+
+pub fn format_project(config: &ProjectConfig, check: bool, diff: bool) -> Result<()> {
+    let project_root = &config.project_path;
+    let Some(clang_format) = toolchain::find_versioned_program("clang-format") else {
+        bail!("clang-format was not found on PATH. Install it (it ships with LLVM) to use `rig fmt`.");
+    };
+    if !project_root.join(CLANG_FORMAT_FILENAME).exists() {
+        println!(
+            "Note: no {} in the project; clang-format uses its LLVM default style.",
+            CLANG_FORMAT_FILENAME
+        );
+    }
+
+    let files = list_source_files(project_root)?;
+    if files.is_empty() {
+        println!("No C/C++ files to format.");
+        return Ok(());
+    }
+    let unformatted = format_files(&clang_format, project_root, &files)?;
+    let display = |path: &Path| path.strip_prefix(project_root).unwrap_or(path).display().to_string();
+
+    if check {
+        for file in &unformatted {
+            println!("Needs formatting: {}", display(&file.path));
+            if diff {
+                print!("{}", unified_diff(&file.original, &file.formatted, &display(&file.path)));
+            }
+        }
+        if !unformatted.is_empty() {
+            bail!(
+                "{} of {} files are not formatted. Run `rig fmt` to format them.",
+                unformatted.len(),
+                files.len()
+            );
+        }
+        println!("All {} files are formatted.", files.len());
+        return Ok(());
+    }
+
+    for file in &unformatted {
+        if diff {
+            print!("{}", unified_diff(&file.original, &file.formatted, &display(&file.path)));
+        }
+        utils::write(&file.path, &file.formatted)
+            .with_context(|| format!("Failed to write {:?}", file.path))?;
+        println!("Formatted {}", display(&file.path));
+    }
+    println!(
+        "{} of {} files reformatted.",
+        unformatted.len(),
+        files.len()
+    );
+    Ok(())
+}
@@ -0,0 +1,137 @@
+use crate::actions::new::{
+    generate_gitignore_content, generate_main_cpp_content, generate_user_presets_content,
+};
+use crate::cmake::{
+    self, CMAKELISTS_FILENAME, CMAKE_PRESETS_FILENAME, CMAKE_USER_PRESETS_FILENAME,
+};
+use crate::config::{
+    generate_rig_toml_content, ProjectConfig, GITIGNORE_FILENAME, RIG_TOML_FILENAME,
+};
+use crate::toolchain;
+use crate::utils::write_file_content;
+use crate::vcpkg::{self, VCPKG_JSON_FILENAME};
+use anyhow::{bail, Context, Result};
+use std::fs;
+use std::path::PathBuf;
+
+// Tracks what `rig init` did so it can be reported at the end.
+#[derive(Default)]
+struct InitSummary {
+    created: Vec<String>,
+    skipped: Vec<String>,
+}
+
+impl InitSummary {
+    fn print(&self, config: &ProjectConfig) {
+        println!("\nInitialized rig project '{}'.", config.project_name);
+        if self.created.is_empty() {
+            println!("  Nothing to create, all files were already present.");
+        } else {
+            println!("  Created:");
+            for file in &self.created {
+                println!("    + {}", file);
+            }
+        }
+        if !self.skipped.is_empty() {
+            println!("  Skipped (already present, left untouched):");
+            for file in &self.skipped {
+                println!("    = {}", file);
+            }
+        }
+    }
+}
+
+/// Writes `filename` into the project only if it does not exist yet.
+fn write_if_missing(
+    config: &ProjectConfig,
+    summary: &mut InitSummary,
+    filename: &str,
+    generate: impl FnOnce() -> String,
+) -> Result<()> {
+    let path = config.project_path.join(filename);
+    if path.exists() {
+        summary.skipped.push(filename.to_string());
+        return Ok(());
+    }
+    write_file_content(&path, &generate())?;
+    summary.created.push(filename.to_string());
+    Ok(())
+}
+
+pub fn init_project(
+    path: Option<String>,
+    vcpkg_root_override: Option<String>,
+    dependencies: Vec<String>,
+    cpp_standard: String,
+) -> Result<()> {
+    let project_path = match path {
+        Some(p) => PathBuf::from(p),
+        None => std::env::current_dir().context("Failed to get current directory")?,
+    };
+    if !project_path.is_dir() {
+        bail!("Directory not found: {:?}", project_path);
+    }
+    let project_path = fs::canonicalize(&project_path)
+        .with_context(|| format!("Failed to canonicalize project path: {:?}", project_path))?;
+
+    let compiler = toolchain::select_compiler(None)?;
+    let config = ProjectConfig::for_existing_directory(
+        project_path,
+        vcpkg_root_override,
+        dependencies,
+        cpp_standard,
+        compiler.as_ref(),
+    )?;
+
+    println!(
+        "Initializing rig in existing project: {}",
+        config.project_path.display()
+    );
+    let vcpkg_root = config.vcpkg()?.root.clone();
+    println!("Using VCPKG_ROOT: {:?}", vcpkg_root);
+
+    let mut summary = InitSummary::default();
+
+    // vcpkg.json is created by vcpkg itself, so it is handled separately.
+    if config.project_path.join(VCPKG_JSON_FILENAME).exists() {
+        summary.skipped.push(VCPKG_JSON_FILENAME.to_string());
+    } else {
+        vcpkg::initialize_manifest_and_dependencies(&config)?;
+        summary.created.push(VCPKG_JSON_FILENAME.to_string());
+    }
+
+    // Only provide a main source file when we are also providing the
+    // CMakeLists.txt that builds it; an existing CMakeLists.txt has its own sources.
+    let has_cmakelists = config.project_path.join(CMAKELISTS_FILENAME).exists();
+    write_if_missing(&config, &mut summary, CMAKELISTS_FILENAME, || {
+        cmake::generate_cmakelists_content(&config)
+    })?;
+    if !has_cmakelists {
+        write_if_missing(&config, &mut summary, &config.main_cpp_file, || {
+            generate_main_cpp_content(&config.project_name)
+        })?;
+    }
+
+    write_if_missing(
+        &config,
+        &mut summary,
+        CMAKE_PRESETS_FILENAME,
+        || cmake::generate_cmakepresets_content(&[]),
+    )?;
+    let user_presets = generate_user_presets_content(compiler.as_ref(), &vcpkg_root);
+    write_if_missing(&config, &mut summary, CMAKE_USER_PRESETS_FILENAME, || {
+        user_presets
+    })?;
+    write_if_missing(&config, &mut summary, RIG_TOML_FILENAME, || {
+        generate_rig_toml_content(&config)
+    })?;
+    write_if_missing(
+        &config,
+        &mut summary,
+        GITIGNORE_FILENAME,
+        generate_gitignore_content,
+    )?;
+
+    summary.print(&config);
+    Ok(())
+}
@@ -0,0 +1,126 @@
+// src/actions/install.rs
+use crate::actions::build::{build_configured, configure_project, BuildOptions, Reconfigure};
+use crate::config::ProjectConfig;
+use crate::diagnostics::MessageFormat;
+use crate::utils::{self, run_command};
+use anyhow::{bail, Context, Result};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Listed first, in this order; other top-level directories follow alphabetically.
+const INSTALL_KINDS: &[&str] = &["bin", "lib", "include", "share"];
+
+/// Options of `rig install`.
+#[derive(Default)]
+pub struct InstallOptions {
+    /// Install here instead of the preset's `installDir`
+    pub prefix: Option<PathBuf>,
+    /// Install only this component (`cmake --install --component`)
+    pub component: Option<String>,
+}
+
+// CMake lists what it installed in the build directory, one absolute path per line.
+fn read_install_manifest(build_dir: &Path, component: Option<&str>) -> Result<Vec<PathBuf>> {
+    let filename = match component {
+        Some(component) => format!("install_manifest_{}.txt", component),
+        None => "install_manifest.txt".to_string(),
+    };
+    let path = build_dir.join(filename);
+    // Older CMake versions write no manifest when there is nothing to install.
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content =
+        fs::read_to_string(&path).with_context(|| format!("Failed to read {:?}", path))?;
+    Ok(content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(PathBuf::from)
+        .collect())
+}
+
+// Groups the installed files by their top-level directory under `prefix`.
+fn print_installed_files(prefix: &Path, files: &[PathBuf]) {
+    let mut groups: BTreeMap<(usize, String), Vec<String>> = BTreeMap::new();
+    for file in files {
+        let (kind, shown) = match file.strip_prefix(prefix) {
+            Ok(relative) => {
+                let kind = relative
+                    .components()
+                    .next()
+                    .filter(|_| relative.components().count() > 1)
+                    .map(|c| c.as_os_str().to_string_lossy().into_owned())
+                    .unwrap_or_else(|| ".".to_string());
+                (kind, relative.display().to_string())
+            }
+            // Absolute DESTINATIONs install outside the prefix.
+            Err(_) => ("outside the prefix".to_string(), file.display().to_string()),
+        };
+        let order = INSTALL_KINDS
+            .iter()
+            .position(|k| *k == kind)
+            .unwrap_or(INSTALL_KINDS.len());
+        groups.entry((order, kind)).or_default().push(shown);
+    }
+
+    println!("Installed {} files to {}:", files.len(), prefix.display());
+    for ((_, kind), mut entries) in groups {
+        entries.sort();
+        println!("  {} ({})", kind, entries.len());
+        for entry in entries {
+            println!("    {}", entry);
+        }
+    }
+}
+
+/// Builds the project, installs it with `cmake --install` and returns the
+/// install prefix.
+pub fn install_project(config: &ProjectConfig, options: &InstallOptions) -> Result<PathBuf> {
+    let preset_name = config.preset.as_str();
+    let preset = configure_project(config, false, Reconfigure::IfChanged, MessageFormat::Human)
+        .with_context(|| format!("Configuring preset '{}' failed", preset_name))?;
+    build_configured(config, &preset, &BuildOptions::default())
+        .with_context(|| format!("Build process for preset '{}' failed", preset_name))?;
+
+    let prefix = match (&options.prefix, &preset.install_dir) {
+        (Some(prefix), _) => std::path::absolute(prefix)
+            .with_context(|| format!("Invalid install prefix {:?}", prefix))?,
+        (None, Some(install_dir)) => install_dir.clone(),
+        (None, None) => bail!(
+            "Preset '{}' has no installDir. Add one to the preset or pass --prefix.",
+            preset_name
+        ),
+    };
+
+    println!(
+        "Installing preset '{}' to '{}'...",
+        preset_name,
+        prefix.display()
+    );
+    let build_dir = preset.binary_dir.to_string_lossy().into_owned();
+    let prefix_arg = prefix.to_string_lossy().into_owned();
+    let mut args = vec!["--install", &build_dir, "--prefix", &prefix_arg];
+    if let Some(component) = &options.component {
+        args.extend(["--component", component]);
+    }
+    run_command(Path::new("cmake"), &args, Some(&config.project_path))
+        .with_context(|| format!("Install step for preset '{}' failed", preset_name))?;
+    // A manifest left by an earlier install would list the wrong files.
+    if utils::is_dry_run() {
+        return Ok(prefix);
+    }
+
+    let files = read_install_manifest(&preset.binary_dir, options.component.as_deref())?;
+    if files.is_empty() {
+        match &options.component {
+            Some(component) => println!("Component '{}' has no files to install.", component),
+            None => println!(
+                "Nothing was installed. Add install() rules to CMakeLists.txt to install targets."
+            ),
+        }
+    } else {
+        print_installed_files(&prefix, &files);
+    }
+    Ok(prefix)
+}
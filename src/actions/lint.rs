@@ -0,0 +1,237 @@
+// src/actions/lint.rs
+use crate::actions::build::{configure_project, Reconfigure};
+use crate::config::ProjectConfig;
+use crate::diagnostics::{Diagnostic, DiagnosticParser, DiagnosticSummary, MessageFormat, Severity};
+use crate::sarif::{self, ColumnUnit};
+use crate::toolchain;
+use crate::utils::{self, is_project_source, run_parallel, skip_in_dry_run};
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command as OsCommand;
+
+const COMPILE_COMMANDS_FILENAME: &str = "compile_commands.json";
+
+/// Options of `rig lint`.
+pub struct LintOptions {
+    /// Apply clang-tidy's suggested fixes
+    pub fix: bool,
+    /// Overrides the `Checks` of .clang-tidy, e.g. `-*,bugprone-*`
+    pub checks: Option<String>,
+    /// Findings at or above this severity make the command fail
+    pub fail_on: Severity,
+    pub sarif: Option<PathBuf>,
+}
+
+pub(crate) fn generate_clang_tidy_content() -> String {
+    r#"# Checks for `rig lint`; see https://clang.llvm.org/extra/clang-tidy/checks/list.html
+Checks: >
+  -*,
+  bugprone-*,
+  clang-analyzer-*,
+  cppcoreguidelines-*,
+  modernize-*,
+  performance-*,
+  readability-*,
+  -bugprone-easily-swappable-parameters,
+  -cppcoreguidelines-avoid-magic-numbers,
+  -modernize-use-trailing-return-type,
+  -readability-identifier-length,
+  -readability-magic-numbers
+WarningsAsErrors: ''
+FormatStyle: file
+"#
+    .to_string()
+}
+
+#[derive(Deserialize)]
+struct CompileCommand {
+    directory: PathBuf,
+    file: PathBuf,
+}
+
+// The project's own translation units in the compilation database: no
+// dependencies from vcpkg_installed and no sources generated into the build tree.
+fn project_translation_units(project_root: &Path, build_dir: &Path) -> Result<Vec<PathBuf>> {
+    let path = build_dir.join(COMPILE_COMMANDS_FILENAME);
+    let content = fs::read_to_string(&path).with_context(|| {
+        format!(
+            "Failed to read {:?}. The preset must set CMAKE_EXPORT_COMPILE_COMMANDS=ON (the generated presets do).",
+            path
+        )
+    })?;
+    let commands: Vec<CompileCommand> =
+        serde_json::from_str(&content).with_context(|| format!("Failed to parse {:?}", path))?;
+    let build_dir = build_dir.canonicalize().unwrap_or_else(|_| build_dir.to_path_buf());
+
+    let mut files: Vec<PathBuf> = commands
+        .into_iter()
+        .filter_map(|command| command.directory.join(&command.file).canonicalize().ok())
+        .filter(|file| !file.starts_with(&build_dir) && is_project_source(project_root, file))
+        .collect();
+    files.sort();
+    files.dedup();
+    Ok(files)
+}
+
+// Makes `path` a valid part of an LLVM regular expression.
+fn regex_escape(path: &str) -> String {
+    let mut escaped = String::with_capacity(path.len());
+    for c in path.chars() {
+        if ".^$|()[]{}*+?\\".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn run_clang_tidy(
+    clang_tidy: &Path,
+    project_root: &Path,
+    build_dir: &Path,
+    options: &LintOptions,
+    file: &Path,
+) -> Result<Vec<Diagnostic>> {
+    let mut command = OsCommand::new(clang_tidy);
+    command.arg("-p").arg(build_dir).arg("--quiet");
+    // Report findings in the project's headers too; vcpkg headers are dropped afterwards.
+    command.arg(format!(
+        "--header-filter=^{}/",
+        regex_escape(&project_root.to_string_lossy())
+    ));
+    if let Some(checks) = &options.checks {
+        command.arg(format!("--checks={}", checks));
+    }
+    if options.fix {
+        command.arg("--fix");
+    }
+    command.arg(file).current_dir(project_root);
+    if skip_in_dry_run(&command) {
+        return Ok(Vec::new());
+    }
+
+    let output = command
+        .output()
+        .with_context(|| format!("Failed to execute {}", clang_tidy.display()))?;
+    let mut parser = DiagnosticParser::default();
+    let mut diagnostics = Vec::new();
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        diagnostics.extend(parser.feed(line));
+    }
+    diagnostics.extend(parser.finish());
+    // A non-zero exit with diagnostics means compile errors, which are reported as such.
+    if !output.status.success() && diagnostics.is_empty() {
+        bail!(
+            "clang-tidy failed on {:?}: {}",
+            file,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(diagnostics)
+}
+
+fn print_diagnostic(project_root: &Path, diagnostic: &Diagnostic, indent: &str) {
+    let location = match &diagnostic.file {
+        Some(file) => {
+            let file = Path::new(file);
+            let mut location = file.strip_prefix(project_root).unwrap_or(file).display().to_string();
+            for number in [diagnostic.line, diagnostic.column].into_iter().flatten() {
+                location.push_str(&format!(":{}", number));
+            }
+            format!("{}: ", location)
+        }
+        None => String::new(),
+    };
+    let option = diagnostic
+        .option
+        .as_ref()
+        .map(|option| format!(" [{}]", option))
+        .unwrap_or_default();
+    println!(
+        "{}{}{}: {}{}",
+        indent, location, diagnostic.severity, diagnostic.message, option
+    );
+    for note in &diagnostic.notes {
+        print_diagnostic(project_root, note, "    ");
+    }
+}
+
+pub fn lint_project(config: &ProjectConfig, options: &LintOptions) -> Result<()> {
+    let project_root = &config.project_path;
+    let Some(clang_tidy) = toolchain::find_versioned_program("clang-tidy") else {
+        bail!("clang-tidy was not found on PATH. Install it (it ships with LLVM) to use `rig lint`.");
+    };
+
+    // The compilation database is written by the configure step.
+    let preset = configure_project(config, false, Reconfigure::IfChanged, MessageFormat::Human)?;
+    let build_dir = &preset.binary_dir;
+    if utils::is_dry_run() && !build_dir.join(COMPILE_COMMANDS_FILENAME).exists() {
+        eprintln!(
+            "[dry-run] Would run {} on the project sources in {} once it is written.",
+            clang_tidy.display(),
+            COMPILE_COMMANDS_FILENAME
+        );
+        return Ok(());
+    }
+    let files = project_translation_units(project_root, build_dir)?;
+    if files.is_empty() {
+        println!("No project sources in {}.", COMPILE_COMMANDS_FILENAME);
+        return Ok(());
+    }
+
+    // Fixes in shared headers would race, so --fix runs one file at a time.
+    let workers = if options.fix { 1 } else { usize::MAX };
+    println!("Running {} on {} files...", clang_tidy.display(), files.len());
+    let results = run_parallel(&files, workers, |file| {
+        run_clang_tidy(&clang_tidy, project_root, build_dir, options, file)
+    });
+
+    // Findings in headers are reported once, not once per including file.
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+    for result in results {
+        for diagnostic in result? {
+            let in_project = diagnostic
+                .file
+                .as_deref()
+                .is_none_or(|file| is_project_source(project_root, Path::new(file)));
+            if in_project && !diagnostics.iter().any(|d| d.same_as(&diagnostic)) {
+                diagnostics.push(diagnostic);
+            }
+        }
+    }
+
+    let mut summary = DiagnosticSummary::default();
+    for diagnostic in &diagnostics {
+        print_diagnostic(project_root, diagnostic, "");
+        summary.record(diagnostic);
+    }
+    println!("{}", summary);
+
+    if let Some(sarif_path) = &options.sarif {
+        sarif::write_log(
+            sarif_path,
+            project_root,
+            project_root,
+            "clang-tidy",
+            ColumnUnit::Byte,
+            &diagnostics,
+            Vec::new(),
+        )?;
+        println!("Wrote SARIF log to {}", sarif_path.display());
+    }
+
+    let findings = diagnostics
+        .iter()
+        .filter(|d| d.severity.at_least(options.fail_on))
+        .count();
+    if findings > 0 {
+        bail!(
+            "clang-tidy reported {} finding(s) at or above '{}' severity.",
+            findings,
+            options.fail_on
+        );
+    }
+    Ok(())
+}
@@ -3,4 +3,15 @@ pub mod run;
 pub mod build;
 pub mod new;
 pub mod add;
-pub mod clean;
\ No newline at end of file
+pub mod remove;
+pub mod update;
+pub mod clean;
+pub mod init;
+pub mod test;
+pub mod presets;
+pub mod toolchain;
+pub mod fmt;
+pub mod lint;
+pub mod watch;
+pub mod install;
+pub mod dist;
@@ -1,17 +1,25 @@
 // src/actions/new.rs
+use crate::actions::fmt::{generate_clang_format_content, FormatStyle};
+use crate::actions::lint::generate_clang_tidy_content;
 use crate::cmake::{
     self, CMAKELISTS_FILENAME, CMAKE_PRESETS_FILENAME, CMAKE_USER_PRESETS_FILENAME,
 };
 #[allow(unused_imports)]
-use crate::config::{ProjectConfig, GITIGNORE_FILENAME, MAIN_CPP_FILENAME};
-use crate::utils::{handle_project_directory_creation, write_file_content};
+use crate::config::{
+    generate_rig_toml_content, LibraryKind, CLANG_FORMAT_FILENAME, CLANG_TIDY_FILENAME, ProjectConfig, CMAKE_MODULES_DIRNAME,
+    GITIGNORE_FILENAME, INCLUDE_DIRNAME, MAIN_CPP_FILENAME, RIG_TOML_FILENAME, SRC_DIRNAME,
+};
+use crate::sanitizers::Sanitizer;
+use crate::toolchain::{self, Compiler};
+use crate::utils::{self, handle_project_directory_creation, write_file_content};
 use crate::vcpkg;
 use anyhow::{Context, Result};
 use std::fs;
+use std::path::Path;
 
-// Helper function moved from project.rs and made private
+// Helper function moved from project.rs
 // It now takes project_name as an argument to customize the output
-fn generate_main_cpp_content(project_name: &str) -> String {
+pub(crate) fn generate_main_cpp_content(project_name: &str) -> String {
     format!(
         r#"#include <iostream>
 
@@ -36,11 +44,88 @@ int main(int argc, char* argv[]) {{
     )
 }
 
-// Helper function moved from project.rs and made private
-fn generate_gitignore_content() -> String {
+// Public header of a library project, `include/<name>/<name>.h`.
+// Header-only libraries get an inline definition instead of a separate source file.
+fn generate_library_header_content(config: &ProjectConfig, kind: LibraryKind) -> String {
+    let greet = if kind == LibraryKind::HeaderOnly {
+        r#"inline std::string greet(const std::string& name) {
+    return "Hello, " + name + "!";
+}"#
+    } else {
+        "std::string greet(const std::string& name);"
+    };
+    format!(
+        r#"#pragma once
+
+#include <string>
+
+namespace {namespace} {{
+
+{greet}
+
+}}  // namespace {namespace}
+"#,
+        namespace = config.cpp_identifier(),
+        greet = greet
+    )
+}
+
+fn generate_library_source_content(config: &ProjectConfig) -> String {
+    format!(
+        r#"#include "{name}/{name}.h"
+
+namespace {namespace} {{
+
+std::string greet(const std::string& name) {{
+    return "Hello, " + name + "!";
+}}
+
+}}  // namespace {namespace}
+"#,
+        name = config.project_name,
+        namespace = config.cpp_identifier()
+    )
+}
+
+// Writes the include/, src/ and cmake/ layout of a library project.
+fn create_library_files(config: &ProjectConfig, kind: LibraryKind) -> Result<()> {
+    let header_dir = config
+        .project_path
+        .join(INCLUDE_DIRNAME)
+        .join(&config.project_name);
+    utils::create_dir_all(&header_dir)
+        .with_context(|| format!("Failed to create directory: {:?}", header_dir))?;
+    write_file_content(
+        &header_dir.join(format!("{}.h", config.project_name)),
+        &generate_library_header_content(config, kind),
+    )?;
+
+    if kind != LibraryKind::HeaderOnly {
+        let src_dir = config.project_path.join(SRC_DIRNAME);
+        utils::create_dir_all(&src_dir)
+            .with_context(|| format!("Failed to create directory: {:?}", src_dir))?;
+        write_file_content(
+            &src_dir.join(format!("{}.cc", config.project_name)),
+            &generate_library_source_content(config),
+        )?;
+    }
+
+    let cmake_dir = config.project_path.join(CMAKE_MODULES_DIRNAME);
+    utils::create_dir_all(&cmake_dir)
+        .with_context(|| format!("Failed to create directory: {:?}", cmake_dir))?;
+    write_file_content(
+        &cmake::package_config_template_path(config),
+        &cmake::generate_package_config_template(config),
+    )?;
+    Ok(())
+}
+
+// Helper function moved from project.rs
+pub(crate) fn generate_gitignore_content() -> String {
     r#"# CMake
 build/
 install/
+dist/
 CMakeUserPresets.json
 CMakeCache.txt
 CMakeFiles/
@@ -85,6 +170,31 @@ vcpkg_installed/
     .to_string()
 }
 
+/// Generates CMakeUserPresets.json for `compiler` (from `toolchain::select_compiler`)
+/// and the build tool installed on this machine.
+pub(crate) fn generate_user_presets_content(
+    compiler: Option<&Compiler>,
+    vcpkg_root: &Path,
+) -> String {
+    match compiler {
+        Some(compiler) => println!(
+            "Using compiler: {} ({} {})",
+            compiler.name,
+            compiler.family,
+            compiler.version.as_deref().unwrap_or("unknown version")
+        ),
+        None => println!("No C/C++ compiler found on PATH; CMake will pick one when configuring."),
+    }
+    let ninja = toolchain::find_program("ninja");
+    if ninja.is_none() {
+        println!(
+            "Ninja not found on PATH; the dev preset uses the '{}' generator.",
+            toolchain::fallback_generator()
+        );
+    }
+    cmake::generate_cmakeuserpresets_content(vcpkg_root, compiler, ninja.as_deref())
+}
+
 // Helper function moved from project.rs and made private
 fn print_next_steps(config: &ProjectConfig) -> Result<()> {
     println!("\nProject '{}' created successfully!", config.project_name);
@@ -99,26 +209,51 @@ fn print_next_steps(config: &ProjectConfig) -> Result<()> {
     println!("1. `cd {}`", config.project_name);
     println!("2. Configure: `cmake --preset dev`");
     println!("3. Build: `cmake --build --preset dev`");
-    println!(
-        "4. Run your executable (e.g., `./build/dev/{}` or `build\\dev\\{}.exe`)",
-        config.project_name, config.project_name
-    );
+    if config.library_kind.is_some() {
+        println!("4. Install the library and its CMake package: `cmake --install build/dev`");
+    } else {
+        println!(
+            "4. Run your executable (e.g., `./build/dev/{}` or `build\\dev\\{}.exe`)",
+            config.project_name, config.project_name
+        );
+    }
     println!("\nTo build for release (after `dev` preset used once):");
     println!("1. Configure: `cmake --preset release`");
     println!("2. Build: `cmake --build --preset release`");
     Ok(())
 }
 
+/// Options of `rig new` that only shape the generated files.
+pub struct TemplateOptions {
+    /// Sanitizer presets to add to CMakePresets.json
+    pub sanitizers: Vec<Sanitizer>,
+    pub format_style: FormatStyle,
+    /// Write a starter .clang-tidy
+    pub clang_tidy: bool,
+}
+
 pub fn new_project(
     name: String,
     vcpkg_root_override: Option<String>,
     dependencies: Vec<String>,
     cpp_standard: String,
+    library_kind: Option<LibraryKind>,
+    compiler: Option<String>,
+    template: &TemplateOptions,
 ) -> Result<()> {
-    let config = ProjectConfig::new(name, vcpkg_root_override, dependencies, cpp_standard)?;
+    // Detecting compilers runs each one, so it happens once per project.
+    let compiler = toolchain::select_compiler(compiler.as_deref())?;
+    let config = ProjectConfig::new(
+        name,
+        vcpkg_root_override,
+        dependencies,
+        cpp_standard,
+        library_kind,
+        compiler.as_ref(),
+    )?;
 
     println!("Creating new C++ project: {}", config.project_name);
-    println!("Using VCPKG_ROOT: {:?}", config.vcpkg_paths.root);
+    println!("Using VCPKG_ROOT: {:?}", config.vcpkg()?.root);
 
     handle_project_directory_creation(&config.project_path, &config.project_name)?;
 
@@ -131,28 +266,58 @@ pub fn new_project(
         &cmakelists_content,
     )?;
 
-    // Create main.cpp (using the new project_name argument)
-    let main_cpp_content = generate_main_cpp_content(&config.project_name);
-    write_file_content(
-        &config.project_path.join(&config.main_cpp_file), // main_cpp_file comes from config
-        &main_cpp_content,
-    )?;
+    match config.library_kind {
+        // Create the include/ + src/ layout and package config template
+        Some(kind) => create_library_files(&config, kind)?,
+        None => {
+            // Create main.cpp (using the new project_name argument)
+            let main_cpp_content = generate_main_cpp_content(&config.project_name);
+            write_file_content(
+                &config.project_path.join(&config.main_cpp_file), // main_cpp_file comes from config
+                &main_cpp_content,
+            )?;
+        }
+    }
 
     // Create CMakePresets.json
-    let cmakepresets_content = cmake::generate_cmakepresets_content();
+    if template.sanitizers.contains(&Sanitizer::Msan)
+        && !config.compiler.as_deref().is_some_and(|c| c.starts_with("clang"))
+    {
+        println!("Note: the msan preset only works with clang; configure it with CC=clang CXX=clang++.");
+    }
+    let cmakepresets_content = cmake::generate_cmakepresets_content(&template.sanitizers);
     write_file_content(
         &config.project_path.join(CMAKE_PRESETS_FILENAME),
         &cmakepresets_content,
     )?;
 
     // Create CMakeUserPresets.json
-    let cmakeuserpresets_content =
-        cmake::generate_cmakeuserpresets_content(&config.vcpkg_paths.root);
+    let cmakeuserpresets_content = generate_user_presets_content(compiler.as_ref(), &config.vcpkg()?.root);
     write_file_content(
         &config.project_path.join(CMAKE_USER_PRESETS_FILENAME),
         &cmakeuserpresets_content,
     )?;
 
+    // Create rig.toml
+    write_file_content(
+        &config.project_path.join(RIG_TOML_FILENAME),
+        &generate_rig_toml_content(&config),
+    )?;
+
+    // Create .clang-format
+    write_file_content(
+        &config.project_path.join(CLANG_FORMAT_FILENAME),
+        &generate_clang_format_content(template.format_style),
+    )?;
+
+    // Create .clang-tidy
+    if template.clang_tidy {
+        write_file_content(
+            &config.project_path.join(CLANG_TIDY_FILENAME),
+            &generate_clang_tidy_content(),
+        )?;
+    }
+
     // Create .gitignore
     let gitignore_content = generate_gitignore_content();
     write_file_content(
@@ -160,6 +325,10 @@ pub fn new_project(
         &gitignore_content,
     )?;
 
+    if utils::is_dry_run() {
+        println!("\nDry run: project '{}' was not created.", config.project_name);
+        return Ok(());
+    }
     print_next_steps(&config)?;
 
     Ok(())
@@ -0,0 +1,221 @@
+// src/actions/presets.rs
+use crate::cmake::{CMAKE_PRESETS_FILENAME, CMAKE_USER_PRESETS_FILENAME};
+use crate::config::ProjectConfig;
+use crate::presets::{self, print_effective_preset, Presets};
+use crate::utils;
+use anyhow::{bail, Context, Result};
+use serde_json::{json, Map, Value};
+use std::fs;
+use std::path::Path;
+
+/// Options of `rig presets add`.
+pub struct NewPreset {
+    pub name: String,
+    pub display_name: Option<String>,
+    pub inherits: Vec<String>,
+    /// `KEY=VALUE` cache variables
+    pub cache: Vec<String>,
+    /// `KEY=VALUE` environment variables
+    pub env: Vec<String>,
+    /// Write to CMakePresets.json instead of CMakeUserPresets.json
+    pub shared: bool,
+}
+
+fn display_path(project_root: &Path, path: &Path) -> String {
+    path.strip_prefix(project_root)
+        .unwrap_or(path)
+        .display()
+        .to_string()
+}
+
+pub fn list_presets(config: &ProjectConfig) -> Result<()> {
+    let project_root = &config.project_path;
+    let presets = Presets::load(project_root)?;
+
+    let rows: Vec<[String; 6]> = presets
+        .configure_presets()
+        .map(|(preset, file)| {
+            let binary_dir = presets
+                .resolve(&preset.name)
+                .map(|p| display_path(project_root, &p.binary_dir))
+                .unwrap_or_else(|_| "-".to_string());
+            let inherits = preset.inherits().join(", ");
+            [
+                preset.name.clone(),
+                preset.display_name.clone().unwrap_or_default(),
+                display_path(project_root, file),
+                if preset.hidden { "yes" } else { "" }.to_string(),
+                inherits,
+                binary_dir,
+            ]
+        })
+        .collect();
+
+    if rows.is_empty() {
+        println!("No configure presets found.");
+        return Ok(());
+    }
+
+    let header = ["NAME", "DISPLAY NAME", "FILE", "HIDDEN", "INHERITS", "BINARY DIR"];
+    let mut widths = header.map(str::len);
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+    let format_row = |cells: [&str; 6]| {
+        let padded: Vec<String> = cells
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{:<width$}", cell))
+            .collect();
+        padded.join("  ").trim_end().to_string()
+    };
+    println!("{}", format_row(header));
+    for row in &rows {
+        println!("{}", format_row(row.each_ref().map(String::as_str)));
+    }
+    Ok(())
+}
+
+pub fn show_preset(config: &ProjectConfig, name: &str) -> Result<()> {
+    let project_root = &config.project_path;
+    let presets = Presets::load(project_root)?;
+    let Some((preset, file)) = presets.configure_presets().find(|(p, _)| p.name == name) else {
+        bail!(
+            "No configure preset named '{}'. Run `rig presets list` to see the available presets.",
+            name
+        );
+    };
+
+    match &preset.display_name {
+        Some(display_name) => println!("Preset '{}' ({}):", preset.name, display_name),
+        None => println!("Preset '{}':", preset.name),
+    }
+    println!("  file: {}", display_path(project_root, file));
+    if preset.hidden {
+        println!("  hidden: yes");
+    }
+    if !preset.inherits().is_empty() {
+        println!("  inherits: {}", preset.inherits().join(", "));
+    }
+    print_effective_preset(&presets.resolve(name)?);
+    Ok(())
+}
+
+fn parse_assignments(assignments: &[String], flag: &str) -> Result<Map<String, Value>> {
+    let mut map = Map::new();
+    for assignment in assignments {
+        let Some((key, value)) = assignment.split_once('=') else {
+            bail!("Invalid {} '{}': expected KEY=VALUE", flag, assignment);
+        };
+        if key.is_empty() {
+            bail!("Invalid {} '{}': the key is empty", flag, assignment);
+        }
+        map.insert(key.to_string(), Value::String(value.to_string()));
+    }
+    Ok(map)
+}
+
+fn configure_preset_json(preset: &NewPreset) -> Result<Value> {
+    let mut object = Map::new();
+    object.insert("name".to_string(), json!(preset.name));
+    if let Some(display_name) = &preset.display_name {
+        object.insert("displayName".to_string(), json!(display_name));
+    }
+    match preset.inherits.as_slice() {
+        [] => {
+            // Without a parent there is nothing to inherit a build directory from.
+            object.insert(
+                "binaryDir".to_string(),
+                json!("${sourceDir}/build/${presetName}"),
+            );
+        }
+        [parent] => {
+            object.insert("inherits".to_string(), json!(parent));
+        }
+        parents => {
+            object.insert("inherits".to_string(), json!(parents));
+        }
+    }
+    let cache_variables = parse_assignments(&preset.cache, "--cache")?;
+    if !cache_variables.is_empty() {
+        object.insert("cacheVariables".to_string(), Value::Object(cache_variables));
+    }
+    let environment = parse_assignments(&preset.env, "--env")?;
+    if !environment.is_empty() {
+        object.insert("environment".to_string(), Value::Object(environment));
+    }
+    Ok(Value::Object(object))
+}
+
+pub fn add_preset(config: &ProjectConfig, preset: &NewPreset) -> Result<()> {
+    let project_root = &config.project_path;
+    let presets = Presets::load(project_root)?;
+
+    if presets.configure_preset_origin(&preset.name).is_some() {
+        bail!("A configure preset named '{}' already exists.", preset.name);
+    }
+    for parent in &preset.inherits {
+        match presets.configure_preset_origin(parent) {
+            None => bail!("Cannot inherit from unknown preset '{}'.", parent),
+            Some(true) if preset.shared => bail!(
+                "Presets in {} cannot inherit from '{}', which is defined in {}.",
+                CMAKE_PRESETS_FILENAME,
+                parent,
+                CMAKE_USER_PRESETS_FILENAME
+            ),
+            Some(_) => {}
+        }
+    }
+
+    let filename = if preset.shared {
+        CMAKE_PRESETS_FILENAME
+    } else {
+        CMAKE_USER_PRESETS_FILENAME
+    };
+    let path = project_root.join(filename);
+    let existed = path.exists();
+    let original = if existed {
+        fs::read_to_string(&path).with_context(|| format!("Failed to read {:?}", path))?
+    } else {
+        "{\n    \"version\": 3\n}\n".to_string()
+    };
+
+    let link = json!({ "name": preset.name, "configurePreset": preset.name });
+    let mut content = presets::append_to_array(
+        &original,
+        "configurePresets",
+        &configure_preset_json(preset)?,
+    )?;
+    if !presets.has_build_preset(&preset.name) {
+        content = presets::append_to_array(&content, "buildPresets", &link)?;
+    }
+    if !presets.has_test_preset(&preset.name) {
+        content = presets::append_to_array(&content, "testPresets", &link)?;
+    }
+    utils::write(&path, &content).with_context(|| format!("Failed to write {:?}", path))?;
+    // Nothing was written, so there is nothing to check the preset against.
+    if utils::is_dry_run() {
+        return Ok(());
+    }
+
+    // Check that CMake will be able to use the preset; undo the change if not.
+    let resolved = Presets::load(project_root).and_then(|p| p.configure_preset(&preset.name));
+    let effective = match resolved {
+        Ok(effective) => effective,
+        Err(err) => {
+            if existed {
+                utils::write(&path, &original)
+                    .with_context(|| format!("Failed to restore {:?}", path))?;
+            } else {
+                utils::remove_file(&path).with_context(|| format!("Failed to remove {:?}", path))?;
+            }
+            return Err(err.context(format!("Preset '{}' was not added", preset.name)));
+        }
+    };
+
+    println!("Added preset '{}' to {}.", preset.name, filename);
+    print_effective_preset(&effective);
+    Ok(())
+}
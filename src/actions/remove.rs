@@ -0,0 +1,177 @@
+use crate::cmake::{self, ManagedCMakeLists, CMAKELISTS_FILENAME};
+use crate::config::ProjectConfig;
+use crate::manifest::VcpkgManifest;
+use crate::utils::{self, collect_source_files};
+use crate::vcpkg::{self, VCPKG_JSON_FILENAME};
+use anyhow::{bail, Context, Result};
+use std::fs;
+use std::path::Path;
+
+// Removes the ports from the manifest's `dependencies`, both plain string
+// entries and object-form entries (`{ "name": ..., "features": [...] }`).
+// Returns the ports that were actually found.
+fn remove_from_manifest(manifest_path: &Path, ports: &[String]) -> Result<Vec<String>> {
+    let mut manifest = VcpkgManifest::load(manifest_path)?;
+    let removed: Vec<String> = ports
+        .iter()
+        .filter(|port| manifest.remove_dependency(port))
+        .cloned()
+        .collect();
+    if !removed.is_empty() {
+        manifest.save(manifest_path)?;
+    }
+    Ok(removed)
+}
+
+fn remove_from_cmakelists(project_root: &Path, ports: &[String]) -> Result<()> {
+    let cmakelists_path = project_root.join(CMAKELISTS_FILENAME);
+    if !cmakelists_path.exists() {
+        return Ok(());
+    }
+    let content = fs::read_to_string(&cmakelists_path)
+        .with_context(|| format!("Failed to read {:?}", cmakelists_path))?;
+
+    let Some(mut managed) = ManagedCMakeLists::parse(&content)
+        .with_context(|| format!("Cannot update {:?}", cmakelists_path))?
+    else {
+        println!(
+            "Warning: {} has no rig-managed dependency block; remove the find_package/target_link_libraries lines for {:?} by hand.",
+            CMAKELISTS_FILENAME, ports
+        );
+        return Ok(());
+    };
+
+    let removed: Vec<&String> = ports.iter().filter(|port| managed.remove(port)).collect();
+    if removed.is_empty() {
+        println!(
+            "No entries for {:?} in the rig-managed block of {}.",
+            ports, CMAKELISTS_FILENAME
+        );
+        return Ok(());
+    }
+    utils::write(&cmakelists_path, managed.render())
+        .with_context(|| format!("Failed to write {:?}", cmakelists_path))?;
+    println!("Updated {} (removed {:?}).", CMAKELISTS_FILENAME, removed);
+    Ok(())
+}
+
+// Drops the find_dependency lines of the ports from the rig-managed block of a
+// library's `<name>Config.cmake.in`.
+fn remove_from_package_config(config: &ProjectConfig, ports: &[String]) -> Result<()> {
+    let template_path = cmake::package_config_template_path(config);
+    if !template_path.exists() {
+        return Ok(());
+    }
+    let content = fs::read_to_string(&template_path)
+        .with_context(|| format!("Failed to read {:?}", template_path))?;
+    let Some(mut managed) = ManagedCMakeLists::parse(&content)
+        .with_context(|| format!("Cannot update {:?}", template_path))?
+    else {
+        println!(
+            "Warning: {:?} has no rig-managed dependency block; remove the find_dependency calls for {:?} by hand.",
+            template_path, ports
+        );
+        return Ok(());
+    };
+
+    let removed: Vec<&String> = ports.iter().filter(|port| managed.remove(port)).collect();
+    if !removed.is_empty() {
+        utils::write(&template_path, managed.render())
+            .with_context(|| format!("Failed to write {:?}", template_path))?;
+        let relative = template_path.strip_prefix(&config.project_path).unwrap_or(&template_path);
+        println!("Updated {} (removed {:?}).", relative.display(), removed);
+    }
+    Ok(())
+}
+
+// Include paths that most likely belong to `port` when vcpkg's file lists are
+// not available, e.g. `fmt/...` for fmt or `nlohmann/...` for nlohmann-json.
+fn guessed_include_prefixes(port: &str) -> Vec<String> {
+    let mut prefixes = vec![format!("{}/", port), format!("{}/", port.replace('-', "_"))];
+    if let Some((first, _)) = port.split_once('-') {
+        prefixes.push(format!("{}/", first));
+    }
+    prefixes
+}
+
+fn included_path(line: &str) -> Option<&str> {
+    let rest = line.trim_start().strip_prefix('#')?.trim_start();
+    let rest = rest.strip_prefix("include")?.trim_start();
+    let (open, close) = match rest.chars().next()? {
+        '<' => ('<', '>'),
+        '"' => ('"', '"'),
+        _ => return None,
+    };
+    let rest = rest.strip_prefix(open)?;
+    rest.split(close).next()
+}
+
+// Warns about `#include`s under the project sources that still refer to a removed port.
+fn warn_about_remaining_includes(project_root: &Path, ports: &[String]) -> Result<()> {
+    let sources = collect_source_files(project_root)?;
+    for port in ports {
+        let headers = vcpkg::installed_headers(project_root, port);
+        let prefixes = guessed_include_prefixes(port);
+
+        let mut uses = Vec::new();
+        for source in &sources {
+            let Ok(content) = fs::read_to_string(source) else {
+                continue;
+            };
+            for (line_no, line) in content.lines().enumerate() {
+                let Some(included) = included_path(line) else {
+                    continue;
+                };
+                let matches = if headers.is_empty() {
+                    prefixes.iter().any(|p| included.starts_with(p.as_str()))
+                } else {
+                    headers.iter().any(|h| h == included)
+                };
+                if matches {
+                    let display = source.strip_prefix(project_root).unwrap_or(source);
+                    uses.push(format!("{}:{}: {}", display.display(), line_no + 1, line.trim()));
+                }
+            }
+        }
+
+        if !uses.is_empty() {
+            println!(
+                "Warning: '{}' is still included in {} place(s):",
+                port,
+                uses.len()
+            );
+            for location in uses {
+                println!("  {}", location);
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn remove_dependencies(config: &ProjectConfig, dependencies_to_remove: &[String]) -> Result<()> {
+    if dependencies_to_remove.is_empty() {
+        bail!("No dependencies specified to remove.");
+    }
+
+    let project_root = &config.project_path;
+    println!("Operating in project root: {}", project_root.display());
+
+    let removed = remove_from_manifest(
+        &project_root.join(VCPKG_JSON_FILENAME),
+        dependencies_to_remove,
+    )?;
+    for port in dependencies_to_remove {
+        if !removed.contains(port) {
+            println!("'{}' is not a dependency in {}.", port, VCPKG_JSON_FILENAME);
+        }
+    }
+    if !removed.is_empty() {
+        println!("Removed {:?} from {}.", removed, VCPKG_JSON_FILENAME);
+    }
+
+    remove_from_cmakelists(project_root, dependencies_to_remove)?;
+    remove_from_package_config(config, dependencies_to_remove)?;
+    warn_about_remaining_includes(project_root, dependencies_to_remove)?;
+
+    Ok(())
+}
@@ -1,83 +1,130 @@
 // src/actions/run.rs
-use crate::actions::build::build_project;
-use crate::utils::find_project_root_by_marker; // Import the new utility
-use crate::vcpkg::VCPKG_JSON_FILENAME; // For marker
+use crate::actions::build::{build_configured, configure_project, BuildOptions, Reconfigure};
+use crate::config::ProjectConfig;
+use crate::diagnostics::MessageFormat;
+use crate::fileapi::{self, ExecutableTarget};
+use crate::sanitizers;
+use crate::utils;
 use anyhow::{bail, Context, Result};
-use std::path::PathBuf;
 use std::process::Command as OsCommand;
-use std::fs;
 
-// Modified find_project_name, assumes project_root is already found
-fn determine_project_name(project_root: &PathBuf, target_override: Option<String>) -> Result<String> {
-    if let Some(name) = target_override {
-        return Ok(name);
+// Picks the executable to run: `--bin`, else the first rig.toml target that is
+// an executable, else the only executable. Several candidates are an error.
+fn select_executable(
+    config: &ProjectConfig,
+    executables: Vec<ExecutableTarget>,
+    bin: Option<String>,
+) -> Result<ExecutableTarget> {
+    let names = || {
+        executables
+            .iter()
+            .map(|e| format!("  {}", e.name))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    if executables.is_empty() {
+        bail!("The project has no executable targets for preset '{}'.", config.preset);
     }
-
-    let vcpkg_json_path = project_root.join(VCPKG_JSON_FILENAME);
-    if vcpkg_json_path.exists() {
-        let content = fs::read_to_string(&vcpkg_json_path)
-            .with_context(|| format!("Failed to read {} at {:?}", VCPKG_JSON_FILENAME, vcpkg_json_path))?;
-        if let Some(line) = content.lines().find(|l| l.trim().starts_with(r#""name":"#)) {
-            let name_part = line.split(':').nth(1).unwrap_or("").trim();
-            let name = name_part.trim_matches(|c| c == '"' || c == ',').to_string();
-            if !name.is_empty() { return Ok(name); }
-        }
+    if let Some(bin) = bin {
+        return match executables.iter().position(|e| e.name == bin) {
+            Some(index) => Ok(executables[index].clone()),
+            None => bail!(
+                "No executable target named '{}'. Available executables:\n{}",
+                bin,
+                names()
+            ),
+        };
+    }
+    if let Some(target) = config
+        .targets
+        .iter()
+        .find_map(|t| executables.iter().find(|e| &e.name == t))
+    {
+        return Ok(target.clone());
     }
-    // Fallback to directory name if not in vcpkg.json or vcpkg.json doesn't exist
-    project_root.file_name().and_then(|name| name.to_str()).map(String::from)
-        .context("Failed to determine project name from project directory")
+    if executables.len() == 1 {
+        return Ok(executables[0].clone());
+    }
+    bail!(
+        "The project has several executables; pick one with `rig run --bin <TARGET>`:\n{}",
+        names()
+    )
 }
 
-
-pub fn run_project(
-    preset: &str,
-    target_override: Option<String>,
+/// Builds the executable to run and returns the command that runs it with
+/// `args` (or rig.toml's run args), from the project root.
+pub fn prepare_run(
+    config: &ProjectConfig,
+    bin: Option<String>,
     clean_build_first: bool,
     args: &[String],
-) -> Result<()> {
+) -> Result<OsCommand> {
+    let preset = config.preset.as_str();
     println!("Ensuring project is built before running...");
-    build_project(preset, clean_build_first)
+    let effective = configure_project(config, clean_build_first, Reconfigure::IfChanged, MessageFormat::Human)
+        .with_context(|| format!("Configuring preset '{}' failed", preset))?;
+
+    let project_root = &config.project_path;
+    let executables = match fileapi::executable_targets(&effective.binary_dir) {
+        // The File API reply only exists once CMake has really configured the tree.
+        Err(e) if utils::is_dry_run() => {
+            return Err(e.context("--dry-run cannot pick the executable before the project is configured"));
+        }
+        result => result?,
+    };
+    let target = select_executable(config, executables, bin)?;
+    // Only the executable about to run needs to be up to date.
+    let options = BuildOptions {
+        targets: vec![target.name.clone()],
+        ..BuildOptions::default()
+    };
+    build_configured(config, &effective, &options)
         .with_context(|| format!("Build process for preset '{}' failed", preset))?;
     println!("Build check complete.");
-
-    // Find project root
-    let project_root = find_project_root_by_marker(VCPKG_JSON_FILENAME) // Or CMAKELISTS_FILENAME
-        .context("Failed to find project root. Are you in a project directory?")?;
-    
-    // Determine project name
-    let project_name = determine_project_name(&project_root, target_override)
-        .context("Failed to determine project name.")?;
-
+    // rig.toml's run args apply only when none are given on the command line
+    let args = if args.is_empty() { &config.run_args[..] } else { args };
 
     println!(
         "Attempting to run target '{}' from project at '{}' using preset '{}'...",
-        project_name,
+        target.name,
         project_root.display(),
         preset
     );
 
-    let mut exe_path = project_root.join("build").join(preset).join(&project_name);
-    if cfg!(windows) {
-        exe_path.set_extension("exe");
-    }
-
-    if !exe_path.exists() {
+    let exe_path = target.artifact;
+    if !exe_path.exists() && !utils::is_dry_run() {
         bail!(
-            "Executable not found at '{}'. Check target name and CMakeLists.txt.",
+            "Executable for target '{}' not found at '{}'. Was it built?",
+            target.name,
             exe_path.display()
         );
     }
 
-    println!("Executing: {} {}", exe_path.display(), args.join(" "));
-    println!("================================================");
-
     let mut command = OsCommand::new(&exe_path);
     command.args(args);
-    command.current_dir(&project_root); // Executing from project root is fine
+    command.current_dir(project_root); // Executing from project root is fine
+    sanitizers::configure_runtime(&mut command, &effective);
+    if !utils::is_dry_run() {
+        println!("Executing: {} {}", exe_path.display(), args.join(" "));
+        println!("================================================");
+    }
+    Ok(command)
+}
 
+pub fn run_project(
+    config: &ProjectConfig,
+    bin: Option<String>,
+    clean_build_first: bool,
+    args: &[String],
+) -> Result<()> {
+    let mut command = prepare_run(config, bin, clean_build_first, args)?;
+    if utils::skip_in_dry_run(&command) {
+        return Ok(());
+    }
+    let program = command.get_program().to_string_lossy().into_owned();
     let status = command
         .status()
-        .with_context(|| format!("Failed to execute command: {}", exe_path.display()))?;
+        .with_context(|| format!("Failed to execute command: {}", program))?;
 
     if !status.success() {
         if let Some(code) = status.code() {
@@ -89,4 +136,4 @@ pub fn run_project(
     }
 
     Ok(())
-}
\ No newline at end of file
+}
@@ -0,0 +1,231 @@
+use crate::actions::build::{build_project, BuildOptions, Reconfigure};
+use crate::config::ProjectConfig;
+use crate::presets::Presets;
+use crate::sanitizers;
+use crate::utils;
+use anyhow::{bail, Context, Result};
+use std::io::{BufRead, BufReader};
+use std::process::{Command as OsCommand, Stdio};
+
+/// Test selection options forwarded to ctest.
+#[derive(Default)]
+pub struct TestFilters {
+    pub include_regex: Option<String>,
+    pub exclude_regex: Option<String>,
+    pub label_regex: Option<String>,
+    pub exclude_label_regex: Option<String>,
+}
+
+#[derive(Default)]
+struct TestSummary {
+    passed: usize,
+    failed: usize,
+    skipped: usize,
+    failed_names: Vec<String>,
+}
+
+// The text after `Test`, whitespace and `#`. CTest pads the number to the
+// width of the largest one, so suites of 10 or more tests print `Test  #3`.
+fn after_test_marker(line: &str) -> Option<&str> {
+    line.match_indices("Test").find_map(|(pos, word)| {
+        let after = &line[pos + word.len()..];
+        let rest = after.trim_start();
+        if rest.len() == after.len() {
+            return None;
+        }
+        rest.strip_prefix('#')
+    })
+}
+
+impl TestSummary {
+    // Parses a ctest result line such as
+    // `2/3 Test #2: bar ..........***Failed    0.01 sec`.
+    fn record_line(&mut self, line: &str) {
+        let Some(rest) = after_test_marker(line) else {
+            return;
+        };
+        let Some(colon) = rest.find(": ") else {
+            return;
+        };
+        let rest = &rest[colon + 2..];
+        let Some(dots) = rest.find(" .") else {
+            return;
+        };
+        let name = rest[..dots].trim().to_string();
+        let status = rest[dots..]
+            .trim_start_matches([' ', '.'])
+            .trim_start_matches('*');
+        // Strip the trailing timing, e.g. "Failed    0.01 sec".
+        let status = status
+            .rsplit_once(" sec")
+            .map(|(s, _)| s)
+            .unwrap_or(status)
+            .trim_end_matches(|c: char| c.is_ascii_digit() || c == '.')
+            .trim();
+
+        match status {
+            "Passed" => self.passed += 1,
+            s if s == "Skipped" || s == "Disabled" || s.starts_with("Not Run") => {
+                self.skipped += 1
+            }
+            _ => {
+                self.failed += 1;
+                self.failed_names.push(format!("{} ({})", name, status));
+            }
+        }
+    }
+
+    fn print(&self) {
+        println!("================================================");
+        println!(
+            "Test summary: {} passed, {} failed, {} skipped ({} total)",
+            self.passed,
+            self.failed,
+            self.skipped,
+            self.passed + self.failed + self.skipped
+        );
+        if !self.failed_names.is_empty() {
+            println!("Failed tests:");
+            for name in &self.failed_names {
+                println!("  - {}", name);
+            }
+        }
+    }
+}
+
+pub fn test_project(
+    config: &ProjectConfig,
+    no_build: bool,
+    clean_build_first: bool,
+    filters: &TestFilters,
+    ctest_args: &[String],
+) -> Result<()> {
+    let preset_name = config.preset.as_str();
+    if no_build {
+        println!("Skipping build (--no-build).");
+    } else {
+        println!("Ensuring project is built before testing...");
+        build_project(
+            config,
+            clean_build_first,
+            Reconfigure::IfChanged,
+            &BuildOptions::default(),
+        )
+        .with_context(|| format!("Build process for preset '{}' failed", preset_name))?;
+    }
+
+    let project_root = &config.project_path;
+
+    let mut command = OsCommand::new("ctest");
+    command.arg("--preset").arg(preset_name);
+    if let Some(regex) = &filters.include_regex {
+        command.arg("-R").arg(regex);
+    }
+    if let Some(regex) = &filters.exclude_regex {
+        command.arg("-E").arg(regex);
+    }
+    if let Some(regex) = &filters.label_regex {
+        command.arg("-L").arg(regex);
+    }
+    if let Some(regex) = &filters.exclude_label_regex {
+        command.arg("-LE").arg(regex);
+    }
+    command.args(ctest_args);
+    command.current_dir(project_root);
+    // ctest passes its environment on to the test executables.
+    let effective = Presets::load(project_root)?.configure_preset(preset_name)?;
+    sanitizers::configure_runtime(&mut command, &effective);
+
+    println!("Running tests with CTest using preset '{}'...", preset_name);
+    if utils::skip_in_dry_run(&command) {
+        return Ok(());
+    }
+
+    // Stdout is captured so the results can be summarized, and echoed as it arrives.
+    let mut child = command
+        .stdout(Stdio::piped())
+        .spawn()
+        .context("Failed to execute ctest. Is CMake installed and on your PATH?")?;
+
+    let mut summary = TestSummary::default();
+    if let Some(stdout) = child.stdout.take() {
+        for line in BufReader::new(stdout).lines() {
+            let line = line.context("Failed to read ctest output")?;
+            println!("{}", line);
+            summary.record_line(&line);
+        }
+    }
+
+    let status = child.wait().context("Failed to wait for ctest")?;
+    summary.print();
+
+    if !status.success() {
+        bail!(
+            "Tests failed for preset '{}' (ctest exit code: {:?})",
+            preset_name,
+            status.code()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summarize(output: &str) -> TestSummary {
+        let mut summary = TestSummary::default();
+        for line in output.lines() {
+            summary.record_line(line);
+        }
+        summary
+    }
+
+    #[test]
+    fn counts_results_of_small_suites() {
+        let summary = summarize(
+            "    Start 1: foo
+1/3 Test #1: foo ..............................   Passed    0.00 sec
+    Start 2: bar
+2/3 Test #2: bar ..............................***Failed    0.01 sec
+    Start 3: baz
+3/3 Test #3: baz ..............................***Skipped   0.00 sec
+",
+        );
+        assert_eq!((summary.passed, summary.failed, summary.skipped), (1, 1, 1));
+        assert_eq!(summary.failed_names, ["bar (Failed)"]);
+    }
+
+    #[test]
+    fn counts_results_with_padded_test_numbers() {
+        let summary = summarize(
+            "      Start  1: parser_basic
+ 1/12 Test  #1: parser_basic .....................   Passed    0.01 sec
+      Start  9: parser_errors
+ 9/12 Test  #9: parser_errors ....................***Failed    0.02 sec
+      Start 10: lexer_unicode
+10/12 Test #10: lexer_unicode ....................   Passed    0.00 sec
+      Start 11: slow_io
+11/12 Test #11: slow_io ..........................***Not Run (Disabled)   0.00 sec
+      Start 12: timeout
+12/12 Test #12: timeout ..........................***Timeout   1.50 sec
+",
+        );
+        assert_eq!((summary.passed, summary.failed, summary.skipped), (2, 2, 1));
+        assert_eq!(
+            summary.failed_names,
+            ["parser_errors (Failed)", "timeout (Timeout)"]
+        );
+    }
+
+    #[test]
+    fn ignores_other_lines() {
+        let summary = summarize(
+            "Test project /src/app/build/dev
+100% tests passed, 0 tests failed out of 12
+Total Test time (real) =   1.53 sec
+",
+        );
+        assert_eq!(summary.passed + summary.failed + summary.skipped, 0);
+    }
+}
@@ -0,0 +1,158 @@
+// src/actions/toolchain.rs
+use crate::actions::new::generate_user_presets_content;
+use crate::cmake::CMAKE_USER_PRESETS_FILENAME;
+use crate::config::{self, ProjectConfig, DEFAULT_PRESET, RIG_TOML_FILENAME};
+use crate::toolchain::{self, Compiler};
+use crate::utils;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use std::fs;
+use std::path::Path;
+
+pub fn list_toolchains(config: Option<&ProjectConfig>) {
+    let compilers = toolchain::detect_compilers();
+    let current = config.and_then(|c| c.compiler.as_deref());
+
+    if compilers.is_empty() {
+        println!("No C/C++ compilers found on PATH.");
+    } else {
+        let rows: Vec<[String; 5]> = compilers
+            .iter()
+            .map(|c| {
+                [
+                    c.name.clone(),
+                    c.family.to_string(),
+                    c.version.clone().unwrap_or_else(|| "-".to_string()),
+                    format!("{} / {}", c.c, c.cxx),
+                    c.path.display().to_string(),
+                ]
+            })
+            .collect();
+        let header = ["NAME", "FAMILY", "VERSION", "C / C++", "PATH"];
+        let mut widths = header.map(str::len);
+        for row in &rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.len());
+            }
+        }
+        let format_row = |marker: &str, cells: [&str; 5]| {
+            let padded: Vec<String> = cells
+                .iter()
+                .zip(widths)
+                .map(|(cell, width)| format!("{:<width$}", cell))
+                .collect();
+            format!("{} {}", marker, padded.join("  ")).trim_end().to_string()
+        };
+        println!("{}", format_row(" ", header));
+        for row in &rows {
+            let marker = if Some(row[0].as_str()) == current { "*" } else { " " };
+            println!("{}", format_row(marker, row.each_ref().map(String::as_str)));
+        }
+    }
+
+    match toolchain::find_program("ninja") {
+        Some(ninja) => println!("\nninja: {}", ninja.display()),
+        None => println!(
+            "\nninja: not found (presets fall back to the '{}' generator)",
+            toolchain::fallback_generator()
+        ),
+    }
+    if current.is_some() {
+        println!("* compiler selected in {}", RIG_TOML_FILENAME);
+    }
+}
+
+// Points the `dev` preset of an existing CMakeUserPresets.json at `compiler`,
+// keeping any other presets in the file.
+fn update_user_presets(path: &Path, compiler: &Compiler) -> Result<()> {
+    let content =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {:?}", path))?;
+    let mut presets: Value =
+        serde_json::from_str(&content).with_context(|| format!("Failed to parse {:?}", path))?;
+
+    let dev = presets
+        .get_mut("configurePresets")
+        .and_then(Value::as_array_mut)
+        .and_then(|list| {
+            list.iter_mut()
+                .find(|p| p.get("name").and_then(Value::as_str) == Some(DEFAULT_PRESET))
+        })
+        .and_then(Value::as_object_mut)
+        .with_context(|| {
+            format!(
+                "{} has no '{}' configure preset. Delete the file and run `rig toolchain use` again to regenerate it.",
+                CMAKE_USER_PRESETS_FILENAME, DEFAULT_PRESET
+            )
+        })?;
+
+    // Older rig versions put these into `environment`, where CMake ignores them.
+    const TOOL_VARIABLES: [&str; 3] = ["CMAKE_C_COMPILER", "CMAKE_CXX_COMPILER", "CMAKE_MAKE_PROGRAM"];
+    if let Some(environment) = dev.get_mut("environment").and_then(Value::as_object_mut) {
+        for name in TOOL_VARIABLES {
+            environment.remove(name);
+        }
+    }
+
+    let cache_variables = dev
+        .entry("cacheVariables")
+        .or_insert_with(|| Value::Object(Map::new()))
+        .as_object_mut()
+        .context("\"cacheVariables\" of the dev preset is not an object")?;
+    cache_variables.insert("CMAKE_C_COMPILER".to_string(), json!(compiler.c));
+    cache_variables.insert("CMAKE_CXX_COMPILER".to_string(), json!(compiler.cxx));
+    match toolchain::find_program("ninja") {
+        Some(ninja) => {
+            cache_variables.insert(
+                "CMAKE_MAKE_PROGRAM".to_string(),
+                json!(ninja.to_string_lossy()),
+            );
+            dev.remove("generator");
+        }
+        None => {
+            cache_variables.remove("CMAKE_MAKE_PROGRAM");
+            dev.insert(
+                "generator".to_string(),
+                json!(toolchain::fallback_generator()),
+            );
+        }
+    }
+
+    // Same layout as the generated file: four-space indentation.
+    let mut buffer = Vec::new();
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(b"    ");
+    let mut serializer = serde_json::Serializer::with_formatter(&mut buffer, formatter);
+    presets
+        .serialize(&mut serializer)
+        .context("Failed to serialize presets")?;
+    utils::write(path, buffer).with_context(|| format!("Failed to write {:?}", path))
+}
+
+pub fn use_toolchain(config: &ProjectConfig, name: &str) -> Result<()> {
+    let compiler = toolchain::select_compiler(Some(name))?
+        .expect("select_compiler returns the requested compiler or an error");
+    let project_root = &config.project_path;
+
+    config::set_rig_toml_value(
+        &project_root.join(RIG_TOML_FILENAME),
+        "toolchain",
+        "compiler",
+        &compiler.name,
+    )?;
+    println!("Set [toolchain] compiler = \"{}\" in {}.", compiler.name, RIG_TOML_FILENAME);
+
+    let user_presets_path = project_root.join(CMAKE_USER_PRESETS_FILENAME);
+    if user_presets_path.exists() {
+        update_user_presets(&user_presets_path, &compiler)?;
+    } else {
+        let content = generate_user_presets_content(Some(&compiler), &config.vcpkg()?.root);
+        utils::write(&user_presets_path, content)
+            .with_context(|| format!("Failed to write {:?}", user_presets_path))?;
+    }
+    println!(
+        "Updated the '{}' preset in {} to use {} / {}.",
+        DEFAULT_PRESET, CMAKE_USER_PRESETS_FILENAME, compiler.c, compiler.cxx
+    );
+    println!("Run `rig build --clean` to reconfigure existing build directories with the new compiler.");
+    Ok(())
+}
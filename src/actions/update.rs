@@ -0,0 +1,318 @@
+use crate::config::ProjectConfig;
+use crate::manifest::VcpkgManifest;
+use crate::utils;
+use crate::vcpkg::{
+    self, BaselineSource, PortVersion, VcpkgPaths, VCPKG_CONFIGURATION_FILENAME, VCPKG_JSON_FILENAME,
+};
+use anyhow::{bail, Context, Result};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+// Sets `default-registry.baseline` inside a vcpkg configuration object.
+fn set_default_registry_baseline(configuration: &mut Value, baseline: &str) -> Result<()> {
+    let registry = configuration
+        .as_object_mut()
+        .context("vcpkg configuration is not a JSON object")?
+        .entry("default-registry")
+        .or_insert_with(|| Value::Object(Map::new()));
+    registry
+        .as_object_mut()
+        .context("\"default-registry\" is not a JSON object")?
+        .insert("baseline".to_string(), Value::String(baseline.to_string()));
+    Ok(())
+}
+
+const UPSTREAM_VCPKG_REPOSITORY: &str = "https://github.com/microsoft/vcpkg";
+
+fn is_upstream_repository(repository: &str) -> bool {
+    let repository = repository.trim_end_matches('/');
+    let repository = repository.strip_suffix(".git").unwrap_or(repository);
+    repository.eq_ignore_ascii_case(UPSTREAM_VCPKG_REPOSITORY)
+}
+
+// The new baseline is a commit of the local vcpkg checkout, so the default
+// registry must track the same history: a `builtin` registry, a `git` registry
+// on the upstream repository (what `vcpkg new` writes), or a `git` registry
+// whose current baseline is present in the checkout (a fork or mirror).
+// Registries on foreign repositories would end up on a commit they do not contain.
+fn check_default_registry(
+    configuration: Option<&Value>,
+    filename: &str,
+    in_checkout: impl Fn(&str) -> bool,
+) -> Result<()> {
+    let registry = configuration.and_then(|configuration| configuration.get("default-registry"));
+    let field = |name| {
+        registry
+            .and_then(|registry| registry.get(name))
+            .and_then(Value::as_str)
+    };
+    let kind = field("kind");
+    let repository = field("repository");
+    let shares_history = match kind {
+        Some("builtin") => true,
+        Some("git") => {
+            repository.is_some_and(is_upstream_repository)
+                || field("baseline").is_some_and(&in_checkout)
+        }
+        _ => false,
+    };
+    if shares_history {
+        return Ok(());
+    }
+    bail!(
+        "The default registry in {} is of kind '{}'{}, which does not share history with the local vcpkg checkout. `rig update` can only move builtin registries and git registries on the vcpkg repository; update this one with `vcpkg x-update-baseline`.",
+        filename,
+        kind.unwrap_or("unknown"),
+        repository
+            .map(|repository| format!(" ({})", repository))
+            .unwrap_or_default()
+    )
+}
+
+fn ensure_default_registry_matches_checkout(
+    project_root: &Path,
+    manifest: &VcpkgManifest,
+    source: BaselineSource,
+    vcpkg_paths: &VcpkgPaths,
+) -> Result<()> {
+    let (configuration, filename) = match source {
+        BaselineSource::BuiltinBaseline => return Ok(()),
+        BaselineSource::EmbeddedConfiguration => (
+            manifest.extra.get("vcpkg-configuration").cloned(),
+            VCPKG_JSON_FILENAME,
+        ),
+        BaselineSource::ConfigurationFile => {
+            let path = project_root.join(VCPKG_CONFIGURATION_FILENAME);
+            let content = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read {:?}", path))?;
+            let configuration: Value = serde_json::from_str(&content)
+                .with_context(|| format!("Failed to parse {:?}", path))?;
+            (Some(configuration), VCPKG_CONFIGURATION_FILENAME)
+        }
+    };
+    check_default_registry(configuration.as_ref(), filename, |commit| {
+        vcpkg::has_commit(vcpkg_paths, commit)
+    })
+}
+
+fn write_baseline(
+    project_root: &Path,
+    manifest: &mut VcpkgManifest,
+    source: BaselineSource,
+    baseline: &str,
+) -> Result<()> {
+    match source {
+        BaselineSource::BuiltinBaseline => {
+            manifest.builtin_baseline = Some(baseline.to_string());
+            manifest.save(&project_root.join(VCPKG_JSON_FILENAME))?;
+            println!("Updated \"builtin-baseline\" in {}.", VCPKG_JSON_FILENAME);
+        }
+        BaselineSource::EmbeddedConfiguration => {
+            let configuration = manifest
+                .extra
+                .get_mut("vcpkg-configuration")
+                .context("vcpkg.json has no \"vcpkg-configuration\"")?;
+            set_default_registry_baseline(configuration, baseline)?;
+            manifest.save(&project_root.join(VCPKG_JSON_FILENAME))?;
+            println!(
+                "Updated \"vcpkg-configuration.default-registry.baseline\" in {}.",
+                VCPKG_JSON_FILENAME
+            );
+        }
+        BaselineSource::ConfigurationFile => {
+            let path = project_root.join(VCPKG_CONFIGURATION_FILENAME);
+            let content = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read {:?}", path))?;
+            let mut configuration: Value = serde_json::from_str(&content)
+                .with_context(|| format!("Failed to parse {:?}", path))?;
+            set_default_registry_baseline(&mut configuration, baseline)?;
+            let mut serialized = serde_json::to_string_pretty(&configuration)
+                .context("Failed to serialize vcpkg configuration")?;
+            serialized.push('\n');
+            utils::write(&path, serialized).with_context(|| format!("Failed to write {:?}", path))?;
+            println!(
+                "Updated \"default-registry.baseline\" in {}.",
+                VCPKG_CONFIGURATION_FILENAME
+            );
+        }
+    }
+    Ok(())
+}
+
+fn print_version_table(
+    manifest: &VcpkgManifest,
+    old_versions: &BTreeMap<String, PortVersion>,
+    new_versions: &BTreeMap<String, PortVersion>,
+) {
+    let describe = |versions: &BTreeMap<String, PortVersion>, port: &str| {
+        versions
+            .get(port)
+            .map(|v| v.to_string())
+            .unwrap_or_else(|| "-".to_string())
+    };
+    let overridden = |port: &str| {
+        manifest
+            .overrides
+            .as_ref()
+            .is_some_and(|overrides| overrides.iter().any(|o| o.name == port))
+    };
+
+    let rows: Vec<(String, String, String, &str)> = manifest
+        .dependencies()
+        .iter()
+        .map(|dep| {
+            let port = dep.name();
+            let old = describe(old_versions, port);
+            let new = describe(new_versions, port);
+            let note = if overridden(port) {
+                "overridden"
+            } else if old != new {
+                "changed"
+            } else {
+                ""
+            };
+            (port.to_string(), old, new, note)
+        })
+        .collect();
+
+    if rows.is_empty() {
+        println!("No dependencies in {}.", VCPKG_JSON_FILENAME);
+        return;
+    }
+
+    let port_width = rows.iter().map(|r| r.0.len()).max().unwrap_or(0).max(4);
+    let old_width = rows.iter().map(|r| r.1.len()).max().unwrap_or(0).max(3);
+    let new_width = rows.iter().map(|r| r.2.len()).max().unwrap_or(0).max(3);
+
+    println!("{:<port_width$}  {:<old_width$}  NEW", "PORT", "OLD");
+    for (port, old, new, note) in rows {
+        let row = format!(
+            "{:<port_width$}  {:<old_width$}  {:<new_width$}  {}",
+            port, old, new, note
+        );
+        println!("{}", row.trim_end());
+    }
+}
+
+pub fn update_baseline(config: &ProjectConfig) -> Result<()> {
+    let project_root = &config.project_path;
+    let vcpkg_paths = config.vcpkg()?;
+
+    let manifest_path = project_root.join(VCPKG_JSON_FILENAME);
+    let mut manifest = VcpkgManifest::load(&manifest_path)?;
+
+    let Some((source, old_baseline)) = vcpkg::current_baseline(project_root, &manifest) else {
+        bail!(
+            "No baseline found in {} or {}. Add one with `vcpkg x-update-baseline --add-initial-baseline`.",
+            VCPKG_JSON_FILENAME,
+            VCPKG_CONFIGURATION_FILENAME
+        );
+    };
+    ensure_default_registry_matches_checkout(project_root, &manifest, source, vcpkg_paths)?;
+    let new_baseline = vcpkg::checkout_head(vcpkg_paths)?;
+
+    println!("vcpkg checkout: {}", vcpkg_paths.root.display());
+    println!("Current baseline: {}", old_baseline);
+    println!("New baseline:     {}", new_baseline);
+
+    if old_baseline == new_baseline {
+        println!("Baseline is already up to date.");
+        return Ok(());
+    }
+
+    let old_versions = vcpkg::baseline_versions(vcpkg_paths, &old_baseline)?;
+    let new_versions = vcpkg::baseline_versions(vcpkg_paths, &new_baseline)?;
+    println!();
+    print_version_table(&manifest, &old_versions, &new_versions);
+    println!();
+
+    if utils::is_dry_run() {
+        println!("Dry run: no files were modified.");
+        return Ok(());
+    }
+    write_baseline(project_root, &mut manifest, source, &new_baseline)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    // The configuration written by `vcpkg new --application`.
+    fn vcpkg_new_configuration() -> Value {
+        json!({
+            "default-registry": {
+                "kind": "git",
+                "baseline": "0cf34c184ce990471a2d5a7a7b5a5b3cac4cd0a7",
+                "repository": "https://github.com/microsoft/vcpkg"
+            },
+            "registries": [
+                {
+                    "kind": "artifact",
+                    "location": "https://github.com/microsoft/vcpkg-ce-catalog/archive/refs/heads/main.zip",
+                    "name": "microsoft"
+                }
+            ]
+        })
+    }
+
+    fn check(configuration: &Value, in_checkout: bool) -> Result<()> {
+        check_default_registry(Some(configuration), VCPKG_CONFIGURATION_FILENAME, |_| in_checkout)
+    }
+
+    #[test]
+    fn accepts_configuration_from_vcpkg_new() {
+        assert!(check(&vcpkg_new_configuration(), false).is_ok());
+    }
+
+    #[test]
+    fn accepts_upstream_repository_spellings() {
+        for repository in [
+            "https://github.com/microsoft/vcpkg.git",
+            "https://github.com/microsoft/vcpkg/",
+            "https://github.com/Microsoft/vcpkg",
+        ] {
+            let configuration = json!({
+                "default-registry": { "kind": "git", "baseline": "abc", "repository": repository }
+            });
+            assert!(check(&configuration, false).is_ok(), "{}", repository);
+        }
+    }
+
+    #[test]
+    fn accepts_builtin_registry() {
+        let configuration = json!({ "default-registry": { "kind": "builtin", "baseline": "abc" } });
+        assert!(check(&configuration, false).is_ok());
+    }
+
+    #[test]
+    fn accepts_fork_whose_baseline_is_in_checkout() {
+        let configuration = json!({
+            "default-registry": {
+                "kind": "git",
+                "baseline": "abc",
+                "repository": "https://example.com/acme/vcpkg-fork"
+            }
+        });
+        assert!(check(&configuration, true).is_ok());
+    }
+
+    #[test]
+    fn refuses_foreign_registries() {
+        let git = json!({
+            "default-registry": {
+                "kind": "git",
+                "baseline": "abc",
+                "repository": "https://example.com/acme/ports"
+            }
+        });
+        let error = check(&git, false).unwrap_err().to_string();
+        assert!(error.contains("https://example.com/acme/ports"), "{}", error);
+
+        let filesystem = json!({ "default-registry": { "kind": "filesystem", "path": "ports" } });
+        assert!(check(&filesystem, true).is_err());
+        assert!(check_default_registry(None, VCPKG_JSON_FILENAME, |_| true).is_err());
+    }
+}
@@ -0,0 +1,230 @@
+// src/actions/watch.rs
+use crate::actions::build::{build_project, BuildOptions, Reconfigure};
+use crate::actions::run::prepare_run;
+use crate::actions::test::{test_project, TestFilters};
+use crate::cmake::{CMAKELISTS_FILENAME, CMAKE_PRESETS_FILENAME, CMAKE_USER_PRESETS_FILENAME};
+use crate::config::{ProjectConfig, RIG_TOML_FILENAME};
+use crate::presets::Presets;
+use crate::utils::{collect_project_files, is_project_source, is_source_file, skip_in_dry_run};
+use crate::vcpkg::{VCPKG_CONFIGURATION_FILENAME, VCPKG_JSON_FILENAME};
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Child;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+// How many changed files the cycle header names.
+const MAX_LISTED_CHANGES: usize = 5;
+
+/// What `rig watch` does whenever the project changes.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WatchAction {
+    #[default]
+    Build,
+    Test,
+    /// Build and (re)start the executable
+    Run,
+}
+
+/// Options of `rig watch`.
+pub struct WatchOptions {
+    pub action: WatchAction,
+    /// Preset from the command line; it overrides rig.toml on every reload
+    pub preset: Option<String>,
+    /// Executable target to run with `WatchAction::Run`
+    pub bin: Option<String>,
+    /// How long the files must stay unchanged before a cycle starts
+    pub debounce: Duration,
+    /// Passed to the native build tool, ctest or the executable
+    pub args: Vec<String>,
+}
+
+fn is_watched(path: &Path) -> bool {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let build_files = [
+        CMAKELISTS_FILENAME,
+        CMAKE_PRESETS_FILENAME,
+        CMAKE_USER_PRESETS_FILENAME,
+        VCPKG_JSON_FILENAME,
+        VCPKG_CONFIGURATION_FILENAME,
+        RIG_TOML_FILENAME,
+    ];
+    is_source_file(path)
+        || build_files.contains(&name.as_ref())
+        || path.extension().is_some_and(|ext| ext == "cmake")
+}
+
+// Modification time and size of every watched file. Polling keeps this
+// portable; projects are small enough to stat every few hundred milliseconds.
+#[derive(PartialEq, Eq)]
+struct Snapshot(BTreeMap<PathBuf, (Option<SystemTime>, u64)>);
+
+impl Snapshot {
+    fn take(project_root: &Path) -> Result<Snapshot> {
+        let mut files = collect_project_files(project_root, is_watched)?;
+        // Files included by the presets can have any name and live anywhere.
+        if let Ok(presets) = Presets::load(project_root) {
+            files.extend(
+                presets
+                    .files()
+                    .iter()
+                    .filter(|file| !(is_watched(file) && is_project_source(project_root, file)))
+                    .cloned(),
+            );
+        }
+        let entries = files
+            .into_iter()
+            .filter_map(|path| {
+                let metadata = fs::metadata(&path).ok()?;
+                Some((path, (metadata.modified().ok(), metadata.len())))
+            })
+            .collect();
+        Ok(Snapshot(entries))
+    }
+
+    // Files added, modified or removed since `previous`.
+    fn changed_since(&self, previous: &Snapshot) -> Vec<PathBuf> {
+        let mut changed: Vec<PathBuf> = self
+            .0
+            .iter()
+            .filter(|(path, stamp)| previous.0.get(*path) != Some(stamp))
+            .map(|(path, _)| path.clone())
+            .collect();
+        changed.extend(
+            previous
+                .0
+                .keys()
+                .filter(|path| !self.0.contains_key(*path))
+                .cloned(),
+        );
+        changed
+    }
+}
+
+fn print_cycle_header(project_root: &Path, changed: &[PathBuf]) {
+    println!();
+    println!(
+        "================ {} ================",
+        chrono::Local::now().format("%Y-%m-%d %H:%M:%S")
+    );
+    if changed.is_empty() {
+        return;
+    }
+    let mut names: Vec<String> = changed
+        .iter()
+        .take(MAX_LISTED_CHANGES)
+        .map(|path| path.strip_prefix(project_root).unwrap_or(path).display().to_string())
+        .collect();
+    if changed.len() > MAX_LISTED_CHANGES {
+        names.push(format!("and {} more", changed.len() - MAX_LISTED_CHANGES));
+    }
+    println!("Changed: {}", names.join(", "));
+}
+
+// Reports and forgets the child once it has exited on its own.
+fn reap_child(child: &mut Option<Child>) {
+    if let Some(running) = child
+        && let Ok(Some(status)) = running.try_wait()
+    {
+        println!("Process {} exited with {}.", running.id(), status);
+        *child = None;
+    }
+}
+
+fn stop_child(child: &mut Option<Child>) {
+    if let Some(mut running) = child.take() {
+        if let Ok(None) = running.try_wait() {
+            println!("Stopping process {}...", running.id());
+            let _ = running.kill();
+        }
+        let _ = running.wait();
+    }
+}
+
+fn run_cycle(config: &ProjectConfig, options: &WatchOptions, child: &mut Option<Child>) -> Result<()> {
+    match options.action {
+        WatchAction::Build => {
+            let build_options = BuildOptions {
+                native_args: options.args.clone(),
+                ..BuildOptions::default()
+            };
+            build_project(config, false, Reconfigure::IfChanged, &build_options)
+        }
+        WatchAction::Test => {
+            test_project(config, false, false, &TestFilters::default(), &options.args)
+        }
+        WatchAction::Run => {
+            // The previous process keeps running until the new build succeeds.
+            let mut command = prepare_run(config, options.bin.clone(), false, &options.args)?;
+            if skip_in_dry_run(&command) {
+                return Ok(());
+            }
+            stop_child(child);
+            let program = command.get_program().to_string_lossy().into_owned();
+            let spawned = command
+                .spawn()
+                .with_context(|| format!("Failed to execute command: {}", program))?;
+            *child = Some(spawned);
+            Ok(())
+        }
+    }
+}
+
+// Blocks until watched files change and stay unchanged for `debounce`, then
+// returns the changed files.
+fn wait_for_changes(
+    project_root: &Path,
+    snapshot: &mut Snapshot,
+    debounce: Duration,
+    child: &mut Option<Child>,
+) -> Result<Vec<PathBuf>> {
+    loop {
+        thread::sleep(POLL_INTERVAL);
+        reap_child(child);
+        let mut current = Snapshot::take(project_root)?;
+        if current == *snapshot {
+            continue;
+        }
+        // Let bursts (an editor saving several files, a git checkout) settle.
+        loop {
+            thread::sleep(debounce);
+            let next = Snapshot::take(project_root)?;
+            if next == current {
+                break;
+            }
+            current = next;
+        }
+        let changed = current.changed_since(snapshot);
+        *snapshot = current;
+        if !changed.is_empty() {
+            return Ok(changed);
+        }
+    }
+}
+
+pub fn watch_project(options: &WatchOptions) -> Result<()> {
+    let project_root = ProjectConfig::load(options.preset.clone(), None)?.project_path;
+    let project_root = project_root.as_path();
+    let mut snapshot = Snapshot::take(project_root)?;
+    let mut child = None;
+    let mut changed = Vec::new();
+    loop {
+        print_cycle_header(project_root, &changed);
+        // rig.toml is watched, so every cycle starts from its current settings.
+        // A failed cycle is reported and the next change tries again.
+        let cycle = ProjectConfig::load(options.preset.clone(), None)
+            .and_then(|config| run_cycle(&config, options, &mut child));
+        if let Err(e) = cycle {
+            eprintln!("Error: {:#}", e);
+        }
+        println!(
+            "Watching {} files in {} for changes (Ctrl+C to stop)...",
+            snapshot.0.len(),
+            project_root.display()
+        );
+        changed = wait_for_changes(project_root, &mut snapshot, options.debounce, &mut child)?;
+    }
+}
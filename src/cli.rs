@@ -1,9 +1,20 @@
 // src/cli.rs
 use clap::{Parser, Subcommand, Args as ClapArgs};
+use std::path::PathBuf;
+
+use crate::actions::fmt::FormatStyle;
+use crate::actions::watch::WatchAction;
+use crate::config::LibraryKind;
+use crate::diagnostics::{MessageFormat, Severity};
+use crate::sanitizers::Sanitizer;
 
 #[derive(Parser)]
 #[clap(author, version, about = "A CLI tool to create C++/CMake/vcpkg projects", long_about = None)]
 pub struct Args {
+    /// Print the files rig would write or delete and the commands it would run, without doing it
+    #[clap(long, global = true)]
+    pub dry_run: bool,
+
     #[command(subcommand)]
     pub command: CliCommand,
 }
@@ -19,34 +30,191 @@ pub enum CliCommand {
         deps: Vec<String>,
         #[clap(long, default_value = "17")]
         std: String,
+        /// Create a library project instead of an executable
+        #[clap(long)]
+        lib: bool,
+        /// Kind of library to create (requires --lib)
+        #[clap(long, value_enum, default_value = "static", requires = "lib")]
+        kind: LibraryKind,
+        /// Compiler for the dev preset, e.g. gcc-13 (see `rig toolchain list`)
+        #[clap(long)]
+        compiler: Option<String>,
+        /// Add sanitizer presets (asan,ubsan,tsan when no list is given; msan needs clang)
+        #[clap(long, value_enum, value_delimiter = ',', num_args = 0.., default_missing_values = ["asan", "ubsan", "tsan"])]
+        sanitizers: Option<Vec<Sanitizer>>,
+        /// Style of the generated .clang-format
+        #[clap(long, value_enum, default_value = "llvm")]
+        style: FormatStyle,
+        /// Also write a starter .clang-tidy for `rig lint`
+        #[clap(long)]
+        clang_tidy: bool,
+    },
+
+    /// Adopts an existing project in place, generating only the missing rig files
+    Init {
+        /// Project directory (defaults to the current directory)
+        path: Option<String>,
+        #[clap(long)]
+        vcpkg_root: Option<String>,
+        /// Dependencies to add when a new vcpkg.json has to be created
+        #[clap(long, value_delimiter = ',')]
+        deps: Vec<String>,
+        #[clap(long, default_value = "17")]
+        std: String,
     },
 
     /// Builds the project using a CMake preset
     Build {
-        #[clap(long, short, default_value = "dev")]
-        preset: String,
+        /// CMake preset to use (defaults to [build] preset in rig.toml, then "dev")
+        #[clap(long, short)]
+        preset: Option<String>,
         #[clap(long)]
         clean: bool,
+        /// Re-run the CMake configure step even if its inputs are unchanged
+        #[clap(long)]
+        reconfigure: bool,
+        /// Re-run the CMake configure step from an empty cache (cmake --fresh)
+        #[clap(long, conflicts_with = "reconfigure")]
+        fresh: bool,
+        /// Build only this target (repeatable)
+        #[clap(long = "target", short = 't', value_name = "TARGET")]
+        targets: Vec<String>,
+        /// Number of parallel build jobs
+        #[clap(long, short)]
+        jobs: Option<u32>,
+        /// Keep building other targets after an error
+        #[clap(long)]
+        keep_going: bool,
+        /// Show the commands the build tool runs
+        #[clap(long, short)]
+        verbose: bool,
+        /// How to report compiler diagnostics
+        #[clap(long, value_enum, default_value = "human")]
+        message_format: MessageFormat,
+        /// Also write the compiler diagnostics to this file as a SARIF 2.1.0 log
+        #[clap(long, value_name = "FILE")]
+        sarif: Option<PathBuf>,
+        /// Extra arguments passed directly to the native build tool
+        #[clap(last = true)]
+        native_args: Vec<String>,
     },
 
     /// Runs the project's executable (builds first if necessary)
     Run {
-        #[clap(long, short, default_value = "dev")]
-        preset: String,
+        /// CMake preset to use (defaults to [build] preset in rig.toml, then "dev")
         #[clap(long, short)]
-        target: Option<String>,
+        preset: Option<String>,
+        /// Executable target to run (required when the project has several)
+        #[clap(long, short = 't', visible_alias = "target", value_name = "TARGET")]
+        bin: Option<String>,
         #[clap(long)]
         clean: bool,
         #[clap(last = true)]
         executable_args: Vec<String>,
     },
 
+    /// Builds the project and runs its tests with CTest
+    Test {
+        /// CMake preset to use (defaults to [build] preset in rig.toml, then "dev")
+        #[clap(long, short)]
+        preset: Option<String>,
+        /// Run only tests whose names match this regex (ctest -R)
+        #[clap(long, short = 'R', value_name = "REGEX")]
+        tests_regex: Option<String>,
+        /// Exclude tests whose names match this regex (ctest -E)
+        #[clap(long, short = 'E', value_name = "REGEX")]
+        exclude_regex: Option<String>,
+        /// Run only tests with labels matching this regex (ctest -L)
+        #[clap(long, short = 'L', value_name = "REGEX")]
+        label: Option<String>,
+        /// Exclude tests with labels matching this regex (ctest -LE)
+        #[clap(long, value_name = "REGEX")]
+        exclude_label: Option<String>,
+        /// Skip the build step and run the tests as they are
+        #[clap(long)]
+        no_build: bool,
+        #[clap(long)]
+        clean: bool,
+        /// Extra arguments passed directly to ctest
+        #[clap(last = true)]
+        ctest_args: Vec<String>,
+    },
+
+    /// Rebuilds, tests or runs the project whenever its files change
+    Watch {
+        /// What to do on each change
+        #[clap(value_enum, default_value = "build")]
+        action: WatchAction,
+        /// CMake preset to use (defaults to [build] preset in rig.toml, then "dev")
+        #[clap(long, short)]
+        preset: Option<String>,
+        /// Executable target to run with `rig watch run`
+        #[clap(long, short = 't', visible_alias = "target", value_name = "TARGET")]
+        bin: Option<String>,
+        /// Milliseconds the files must stay unchanged before rebuilding
+        #[clap(long, value_name = "MS", default_value = "300")]
+        debounce: u64,
+        /// Extra arguments for the native build tool, ctest or the executable
+        #[clap(last = true)]
+        args: Vec<String>,
+    },
+
+    /// Builds the project and installs it with cmake --install
+    Install {
+        /// CMake preset to use (defaults to [build] preset in rig.toml, then "dev")
+        #[clap(long, short)]
+        preset: Option<String>,
+        /// Install into this directory instead of the preset's installDir
+        #[clap(long, value_name = "DIR")]
+        prefix: Option<PathBuf>,
+        /// Install only this component
+        #[clap(long)]
+        component: Option<String>,
+    },
+
+    /// Packages the installed project and its vcpkg shared libraries as a .tar.gz
+    Dist {
+        /// CMake preset to build and package
+        #[clap(long, short, default_value = "release")]
+        preset: String,
+        /// Directory for the archive and its checksum (defaults to dist/)
+        #[clap(long, short, value_name = "DIR")]
+        output: Option<PathBuf>,
+    },
+
     /// Adds one or more dependencies to the project using vcpkg
     Add {
-        /// Names of the vcpkg ports to add
+        /// vcpkg ports to add, optionally with features and a minimum version,
+        /// e.g. `fmt@10.2.1` or `curl[ssl,http2]`
+        #[clap(required = true, num_args = 1..)]
+        dependencies: Vec<String>,
+
+        /// Path to the VCPKG_ROOT directory (overrides environment variable)
+        #[clap(long)]
+        vcpkg_root: Option<String>,
+
+        /// Do not enable the ports' default features
+        #[clap(long)]
+        no_default_features: bool,
+
+        /// Platform expression restricting the dependencies, e.g. "linux & !arm"
+        #[clap(long)]
+        platform: Option<String>,
+
+        /// Edit CMakeLists.txt even if it has no rig-managed dependency block
+        #[clap(long)]
+        force: bool,
+    },
+
+    /// Removes one or more dependencies from vcpkg.json and CMakeLists.txt
+    Remove {
+        /// Names of the vcpkg ports to remove
         #[clap(required = true, num_args = 1..)]
         dependencies: Vec<String>,
+    },
 
+    /// Moves the vcpkg baseline to the HEAD commit of the local vcpkg checkout
+    Update {
         /// Path to the VCPKG_ROOT directory (overrides environment variable)
         #[clap(long)]
         vcpkg_root: Option<String>,
@@ -54,6 +222,94 @@ pub enum CliCommand {
 
     /// Cleans build artifacts for specified presets or all presets
     Clean(CleanArgs), // Added Clean subcommand
+
+    /// Lists, shows and creates CMake presets
+    Presets {
+        #[command(subcommand)]
+        command: PresetsCommand,
+    },
+
+    /// Lists the installed compilers and selects the one the project uses
+    Toolchain {
+        #[command(subcommand)]
+        command: ToolchainCommand,
+    },
+
+    /// Formats the project's C/C++ sources with clang-format
+    Fmt {
+        /// Only check the formatting; fail if any file would change
+        #[clap(long)]
+        check: bool,
+        /// Print a unified diff of the changes
+        #[clap(long)]
+        diff: bool,
+    },
+
+    /// Runs clang-tidy over the project's sources using the compilation database
+    Lint {
+        /// CMake preset whose compile_commands.json is used (defaults to [build] preset in rig.toml, then "dev")
+        #[clap(long, short)]
+        preset: Option<String>,
+        /// Apply the suggested fixes
+        #[clap(long)]
+        fix: bool,
+        /// clang-tidy checks to run instead of the ones in .clang-tidy, e.g. "-*,bugprone-*"
+        #[clap(long)]
+        checks: Option<String>,
+        /// Fail on findings of this severity or higher
+        #[clap(long, value_enum, default_value = "warning")]
+        fail_on: Severity,
+        /// Also write the findings to this file as a SARIF 2.1.0 log
+        #[clap(long, value_name = "FILE")]
+        sarif: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ToolchainCommand {
+    /// Lists the C/C++ compilers found on PATH
+    List,
+
+    /// Uses a compiler for the project and regenerates the dev preset
+    Use {
+        /// Compiler name as shown by `rig toolchain list`, e.g. gcc-13
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum PresetsCommand {
+    /// Lists the configure presets from CMakePresets.json and CMakeUserPresets.json
+    List,
+
+    /// Shows the fully resolved configure preset
+    Show {
+        name: String,
+    },
+
+    /// Adds a configure preset with matching build and test presets
+    Add {
+        name: String,
+
+        /// Presets to inherit from (repeatable or comma-separated)
+        #[clap(long, value_delimiter = ',')]
+        inherits: Vec<String>,
+
+        /// Cache variable to set (repeatable)
+        #[clap(long, value_name = "KEY=VAL")]
+        cache: Vec<String>,
+
+        /// Environment variable to set (repeatable)
+        #[clap(long, value_name = "KEY=VAL")]
+        env: Vec<String>,
+
+        #[clap(long)]
+        display_name: Option<String>,
+
+        /// Add the preset to CMakePresets.json instead of CMakeUserPresets.json
+        #[clap(long)]
+        shared: bool,
+    },
 }
 
 #[derive(ClapArgs, Debug)] // Added derive Debug
@@ -63,13 +319,17 @@ pub enum CliCommand {
         .args(&["preset", "all"]),
 ))]
 pub struct CleanArgs {
-    /// CMake preset whose build directory to clean
+    /// CMake preset whose build directory to clean (defaults to rig.toml's preset)
     #[clap(long, short)]
     pub preset: Option<String>,
 
     /// Clean all build directories for all presets
     #[clap(long, action = clap::ArgAction::SetTrue)] // Ensures --all acts as a flag
     pub all: bool,
+
+    /// Also remove the install tree (the preset's installDir)
+    #[clap(long)]
+    pub install: bool,
     // Note: clap will ensure 'preset' and 'all' are not used together if not desired,
     // or we can add custom validation logic in the handler.
     // For now, we'll let the action logic handle the precedence (e.g., 'all' overrides 'preset').
@@ -1,26 +1,269 @@
 // src/cmake.rs
-use crate::config::ProjectConfig;
-use std::path::Path;
+use crate::config::{
+    LibraryKind, ProjectConfig, CMAKE_MODULES_DIRNAME, INCLUDE_DIRNAME, SRC_DIRNAME,
+};
+use crate::sanitizers::Sanitizer;
+use crate::toolchain::{self, Compiler};
+use crate::usage;
+use anyhow::{anyhow, bail, Result};
+use std::path::{Path, PathBuf};
 
 pub(crate) const CMAKELISTS_FILENAME: &str = "CMakeLists.txt";
 pub(crate) const CMAKE_PRESETS_FILENAME: &str = "CMakePresets.json";
 pub(crate) const CMAKE_USER_PRESETS_FILENAME: &str = "CMakeUserPresets.json";
 
 
-pub fn generate_cmakelists_content(config: &ProjectConfig) -> String {
-    let find_package_lines = config.dependencies
-        .iter()
-        .filter(|d| !d.is_empty())
-        .map(|dep| format!("find_package({} CONFIG REQUIRED)", dep))
-        .collect::<Vec<_>>()
-        .join("\n");
+pub(crate) const DEPENDENCY_BLOCK_BEGIN: &str = "# >>> rig dependencies >>>";
+pub(crate) const DEPENDENCY_BLOCK_END: &str = "# <<< rig dependencies <<<";
+const DEPENDENCY_BLOCK_NOTE: &str =
+    "# Managed by `rig add` / `rig remove`. Each dependency starts with a `# vcpkg port:` line.";
+const DEPENDENCY_ENTRY_PREFIX: &str = "# vcpkg port: ";
+
+/// How a vcpkg port is consumed from CMake.
+pub struct CMakePackage {
+    /// Arguments of the `find_package` call, e.g. `fmt CONFIG REQUIRED`.
+    pub find_package_args: String,
+    /// Imported targets to link against, e.g. `fmt::fmt`.
+    pub targets: Vec<String>,
+}
+
+impl CMakePackage {
+    /// The conventional `find_package(<port> CONFIG REQUIRED)` / `<port>::<port>` pair.
+    pub fn guess(port: &str) -> Self {
+        Self {
+            find_package_args: format!("{} CONFIG REQUIRED", port),
+            targets: vec![format!("{}::{}", port, port)],
+        }
+    }
+}
+
+/// The target that dependencies get linked to, together with its link scope.
+pub struct LinkTarget {
+    pub name: String,
+    pub scope: &'static str,
+}
+
+impl LinkTarget {
+    pub fn for_config(config: &ProjectConfig) -> Self {
+        let scope = match config.library_kind {
+            None => "PRIVATE",
+            Some(LibraryKind::HeaderOnly) => "INTERFACE",
+            Some(_) => "PUBLIC",
+        };
+        Self {
+            name: config.project_name.clone(),
+            scope,
+        }
+    }
+
+    /// Finds the first `add_executable`/`add_library` target declared in a CMakeLists.txt.
+    pub fn detect(cmakelists: &str) -> Option<Self> {
+        for line in cmakelists.lines() {
+            let line = line.trim_start();
+            let (rest, is_library) = if let Some(rest) = line.strip_prefix("add_executable(") {
+                (rest, false)
+            } else if let Some(rest) = line.strip_prefix("add_library(") {
+                (rest, true)
+            } else {
+                continue;
+            };
+            let mut words = rest.split(|c: char| c.is_whitespace() || c == ')');
+            let name = words.next().filter(|n| !n.is_empty())?;
+            let words: Vec<&str> = words.collect();
+            // ALIAS and IMPORTED targets cannot be linked to.
+            if words.contains(&"ALIAS") || words.contains(&"IMPORTED") {
+                continue;
+            }
+            let scope = if !is_library {
+                "PRIVATE"
+            } else if words.contains(&"INTERFACE") {
+                "INTERFACE"
+            } else {
+                "PUBLIC"
+            };
+            return Some(Self {
+                name: name.to_string(),
+                scope,
+            });
+        }
+        None
+    }
+}
+
+/// One dependency inside the rig-managed block of CMakeLists.txt.
+pub struct DependencyEntry {
+    pub port: String,
+    pub lines: Vec<String>,
+}
+
+impl DependencyEntry {
+    pub fn new(port: &str, package: &CMakePackage, target: &LinkTarget) -> Self {
+        let mut lines = vec![format!("find_package({})", package.find_package_args)];
+        if !package.targets.is_empty() {
+            lines.push(format!(
+                "target_link_libraries({} {} {})",
+                target.name,
+                target.scope,
+                package.targets.join(" ")
+            ));
+        }
+        Self {
+            port: port.to_string(),
+            lines,
+        }
+    }
+}
+
+impl DependencyEntry {
+    /// The `find_dependency` call re-finding `port` for consumers of an installed
+    /// library, as listed in its `<name>Config.cmake.in`.
+    pub fn find_dependency(port: &str, package: &CMakePackage) -> Self {
+        // find_dependency forwards REQUIRED and QUIET from the consumer's find_package.
+        let args: Vec<&str> = package
+            .find_package_args
+            .split_whitespace()
+            .filter(|arg| *arg != "REQUIRED")
+            .collect();
+        Self {
+            port: port.to_string(),
+            lines: vec![format!("find_dependency({})", args.join(" "))],
+        }
+    }
+}
+
+fn render_dependency_block(preamble: &[String], entries: &[DependencyEntry]) -> String {
+    let mut out = format!("{}\n", DEPENDENCY_BLOCK_BEGIN);
+    for line in preamble {
+        out.push_str(line);
+        out.push('\n');
+    }
+    for entry in entries {
+        out.push_str(DEPENDENCY_ENTRY_PREFIX);
+        out.push_str(&entry.port);
+        out.push('\n');
+        for line in &entry.lines {
+            out.push_str(line);
+            out.push('\n');
+        }
+    }
+    out.push_str(DEPENDENCY_BLOCK_END);
+    out.push('\n');
+    out
+}
+
+/// Generates a fresh rig-managed dependency block for the given entries.
+pub fn generate_dependency_block(entries: &[DependencyEntry]) -> String {
+    render_dependency_block(&[DEPENDENCY_BLOCK_NOTE.to_string()], entries)
+}
+
+/// A CMakeLists.txt (or package config template) split around its rig-managed
+/// dependency block.
+pub struct ManagedCMakeLists {
+    head: String,
+    preamble: Vec<String>,
+    pub entries: Vec<DependencyEntry>,
+    tail: String,
+    /// Whether the end marker line was terminated by a newline.
+    end_newline: bool,
+}
+
+impl ManagedCMakeLists {
+    /// Returns `None` if the file has no rig dependency block, and an error if
+    /// the block has no end marker or there is more than one.
+    pub fn parse(content: &str) -> Result<Option<Self>> {
+        let Some(begin) = content.find(DEPENDENCY_BLOCK_BEGIN) else {
+            return Ok(None);
+        };
+        let missing_end = || anyhow!("The rig dependency block has no `{}` line", DEPENDENCY_BLOCK_END);
+        let body_start = begin + content[begin..].find('\n').ok_or_else(missing_end)? + 1;
+        let end = body_start + content[body_start..].find(DEPENDENCY_BLOCK_END).ok_or_else(missing_end)?;
+        if content[body_start..end].contains(DEPENDENCY_BLOCK_BEGIN) {
+            return Err(missing_end());
+        }
+        let (tail_start, end_newline) = match content[end..].find('\n') {
+            Some(i) => (end + i + 1, true),
+            None => (content.len(), false),
+        };
+        if content[tail_start..].contains(DEPENDENCY_BLOCK_BEGIN) {
+            bail!("There is more than one rig dependency block; merge them into one");
+        }
+
+        let mut preamble = Vec::new();
+        let mut entries: Vec<DependencyEntry> = Vec::new();
+        for line in content[body_start..end].lines() {
+            if let Some(port) = line.trim().strip_prefix(DEPENDENCY_ENTRY_PREFIX) {
+                entries.push(DependencyEntry {
+                    port: port.trim().to_string(),
+                    lines: Vec::new(),
+                });
+            } else if let Some(entry) = entries.last_mut() {
+                entry.lines.push(line.to_string());
+            } else {
+                preamble.push(line.to_string());
+            }
+        }
+
+        Ok(Some(Self {
+            head: content[..begin].to_string(),
+            preamble,
+            entries,
+            tail: content[tail_start..].to_string(),
+            end_newline,
+        }))
+    }
+
+    pub fn contains(&self, port: &str) -> bool {
+        self.entries.iter().any(|e| e.port == port)
+    }
+
+    pub fn add(&mut self, entry: DependencyEntry) {
+        self.entries.push(entry);
+    }
+
+    /// Removes the entry for `port`, returning whether it was present.
+    pub fn remove(&mut self, port: &str) -> bool {
+        let before = self.entries.len();
+        self.entries.retain(|e| e.port != port);
+        self.entries.len() != before
+    }
+
+    pub fn render(&self) -> String {
+        let mut block = render_dependency_block(&self.preamble, &self.entries);
+        if !self.end_newline {
+            block.pop();
+        }
+        format!("{}{}{}", self.head, block, self.tail)
+    }
+}
+
+/// Appends a new rig-managed dependency block to a CMakeLists.txt that has none.
+pub fn append_dependency_block(content: &str, entries: &[DependencyEntry]) -> String {
+    let mut out = content.to_string();
+    if !out.is_empty() && !out.ends_with('\n') {
+        out.push('\n');
+    }
+    out.push('\n');
+    out.push_str("# Vcpkg integration\n");
+    out.push_str(&generate_dependency_block(entries));
+    out
+}
 
-    let link_libraries_lines = config.dependencies
+fn dependency_entries(config: &ProjectConfig) -> Vec<DependencyEntry> {
+    let target = LinkTarget::for_config(config);
+    config.dependencies
         .iter()
         .filter(|d| !d.is_empty())
-        .map(|dep| format!("{}::{}", dep, dep))
-        .collect::<Vec<_>>()
-        .join(" ");
+        .map(|dep| {
+            let package = usage::resolve_package(&config.project_path, dep);
+            DependencyEntry::new(dep, &package, &target)
+        })
+        .collect()
+}
+
+pub fn generate_cmakelists_content(config: &ProjectConfig) -> String {
+    if let Some(kind) = config.library_kind {
+        return generate_library_cmakelists_content(config, kind);
+    }
 
     format!(
         r#"cmake_minimum_required(VERSION 3.19)
@@ -30,62 +273,265 @@ set(CMAKE_CXX_STANDARD {cpp_standard})
 set(CMAKE_CXX_STANDARD_REQUIRED ON)
 set(CMAKE_EXPORT_COMPILE_COMMANDS ON)
 
-# Vcpkg integration
-{find_package_lines}
+# Machine-readable fix-it hints for `rig build --message-format json` and `--sarif`
+if(CMAKE_CXX_COMPILER_ID MATCHES "GNU|Clang")
+    add_compile_options(-fdiagnostics-parseable-fixits)
+endif()
+
+include(GNUInstallDirs)
 
 add_executable({project_name} {main_cpp_file})
 
-target_link_libraries({project_name} PRIVATE {link_libraries_lines})
+# Vcpkg integration
+{dependency_block}
+# Installation, used by `rig install` and `rig dist`
+install(TARGETS {project_name} RUNTIME DESTINATION ${{CMAKE_INSTALL_BINDIR}})
 "#,
         project_name = config.project_name,
         main_cpp_file = config.main_cpp_file,
         cpp_standard = config.cpp_standard,
-        find_package_lines = if find_package_lines.is_empty() { "// No dependencies specified".to_string() } else { find_package_lines },
-        link_libraries_lines = if link_libraries_lines.is_empty() { "// No dependencies to link".to_string() } else { link_libraries_lines }
+        dependency_block = generate_dependency_block(&dependency_entries(config)),
+    )
+}
+
+fn generate_library_cmakelists_content(config: &ProjectConfig, kind: LibraryKind) -> String {
+    let name = &config.project_name;
+    let header_only = kind == LibraryKind::HeaderOnly;
+    // Header-only (INTERFACE) libraries can only carry INTERFACE usage requirements.
+    let scope = if header_only { "INTERFACE" } else { "PUBLIC" };
+
+    let sources = if header_only {
+        String::new()
+    } else {
+        format!(" {}/{}.cc", SRC_DIRNAME, name)
+    };
+
+    let version_file_extra = if header_only { "\n    ARCH_INDEPENDENT" } else { "" };
+
+    format!(
+        r#"cmake_minimum_required(VERSION 3.19)
+project({name} VERSION 0.1.0 LANGUAGES CXX)
+
+set(CMAKE_CXX_STANDARD {cpp_standard})
+set(CMAKE_CXX_STANDARD_REQUIRED ON)
+set(CMAKE_EXPORT_COMPILE_COMMANDS ON)
+
+# Machine-readable fix-it hints for `rig build --message-format json` and `--sarif`
+if(CMAKE_CXX_COMPILER_ID MATCHES "GNU|Clang")
+    add_compile_options(-fdiagnostics-parseable-fixits)
+endif()
+
+include(GNUInstallDirs)
+include(CMakePackageConfigHelpers)
+
+add_library({name} {keyword}{sources})
+add_library({name}::{name} ALIAS {name})
+
+target_include_directories({name} {scope}
+    $<BUILD_INTERFACE:${{CMAKE_CURRENT_SOURCE_DIR}}/{include_dir}>
+    $<INSTALL_INTERFACE:${{CMAKE_INSTALL_INCLUDEDIR}}>
+)
+
+# Vcpkg integration
+{dependency_block}
+# Installation and package export, so consumers can `find_package({name})`
+install(TARGETS {name}
+    EXPORT {name}Targets
+    ARCHIVE DESTINATION ${{CMAKE_INSTALL_LIBDIR}}
+    LIBRARY DESTINATION ${{CMAKE_INSTALL_LIBDIR}}
+    RUNTIME DESTINATION ${{CMAKE_INSTALL_BINDIR}}
+)
+install(DIRECTORY {include_dir}/ DESTINATION ${{CMAKE_INSTALL_INCLUDEDIR}})
+install(EXPORT {name}Targets
+    FILE {name}Targets.cmake
+    NAMESPACE {name}::
+    DESTINATION ${{CMAKE_INSTALL_LIBDIR}}/cmake/{name}
+)
+
+configure_package_config_file(
+    {cmake_dir}/{name}Config.cmake.in
+    ${{CMAKE_CURRENT_BINARY_DIR}}/{name}Config.cmake
+    INSTALL_DESTINATION ${{CMAKE_INSTALL_LIBDIR}}/cmake/{name}
+)
+write_basic_package_version_file(
+    ${{CMAKE_CURRENT_BINARY_DIR}}/{name}ConfigVersion.cmake
+    VERSION ${{PROJECT_VERSION}}
+    COMPATIBILITY SameMajorVersion{version_file_extra}
+)
+install(FILES
+    ${{CMAKE_CURRENT_BINARY_DIR}}/{name}Config.cmake
+    ${{CMAKE_CURRENT_BINARY_DIR}}/{name}ConfigVersion.cmake
+    DESTINATION ${{CMAKE_INSTALL_LIBDIR}}/cmake/{name}
+)
+"#,
+        name = name,
+        keyword = kind.cmake_keyword(),
+        sources = sources,
+        scope = scope,
+        cpp_standard = config.cpp_standard,
+        include_dir = INCLUDE_DIRNAME,
+        cmake_dir = CMAKE_MODULES_DIRNAME,
+        dependency_block = generate_dependency_block(&dependency_entries(config)),
+        version_file_extra = version_file_extra,
     )
 }
 
-pub fn generate_cmakepresets_content() -> String {
-    r#"{
+/// The `<name>Config.cmake.in` template of a library project.
+pub fn package_config_template_path(config: &ProjectConfig) -> PathBuf {
+    config
+        .project_path
+        .join(CMAKE_MODULES_DIRNAME)
+        .join(format!("{}Config.cmake.in", config.project_name))
+}
+
+/// Generates the `<name>Config.cmake.in` template consumed by
+/// `configure_package_config_file` in library projects.
+pub fn generate_package_config_template(config: &ProjectConfig) -> String {
+    let entries: Vec<DependencyEntry> = config.dependencies
+        .iter()
+        .filter(|d| !d.is_empty())
+        .map(|dep| {
+            let package = usage::resolve_package(&config.project_path, dep);
+            DependencyEntry::find_dependency(dep, &package)
+        })
+        .collect();
+
+    format!(
+        r#"@PACKAGE_INIT@
+
+include(CMakeFindDependencyMacro)
+{dependency_block}
+include("${{CMAKE_CURRENT_LIST_DIR}}/{name}Targets.cmake")
+
+check_required_components({name})
+"#,
+        name = config.project_name,
+        dependency_block = generate_dependency_block(&entries),
+    )
+}
+
+/// Generates CMakePresets.json with the shared `debug` and `release` presets,
+/// plus one preset per requested sanitizer inheriting from `debug`.
+pub fn generate_cmakepresets_content(sanitizers: &[Sanitizer]) -> String {
+    let mut configure_presets = String::new();
+    let mut build_presets = String::new();
+    let mut test_presets = String::new();
+    for (index, sanitizer) in sanitizers.iter().enumerate() {
+        if sanitizers[..index].contains(sanitizer) {
+            continue;
+        }
+        let name = sanitizer.preset_name();
+        let compile_flags = sanitizer.compile_flags();
+        let link_flags = sanitizer.link_flags();
+        // A separate installed tree keeps dependencies built for this preset
+        // apart from the ones of the uninstrumented presets.
+        configure_presets.push_str(&format!(
+            r#",
+        {{
+            "name": "{name}",
+            "displayName": "{display_name} (Debug)",
+            "inherits": "debug",
+            "cacheVariables": {{
+                "CMAKE_C_FLAGS": "{compile_flags}",
+                "CMAKE_CXX_FLAGS": "{compile_flags}",
+                "CMAKE_EXE_LINKER_FLAGS": "{link_flags}",
+                "CMAKE_SHARED_LINKER_FLAGS": "{link_flags}",
+                "VCPKG_INSTALLED_DIR": "${{sourceDir}}/build/vcpkg_installed/${{presetName}}"
+            }}
+        }}"#,
+            display_name = sanitizer.display_name(),
+        ));
+        build_presets.push_str(&format!(
+            r#",
+        {{ "name": "{name}", "configurePreset": "{name}" }}"#
+        ));
+        test_presets.push_str(&format!(
+            r#",
+      {{ "name": "{name}", "configurePreset": "{name}", "output": {{"outputOnFailure": true}}, "execution": {{"noTestsAction": "error", "stopOnFailure": true}} }}"#
+        ));
+    }
+
+    format!(
+        r#"{{
     "version": 3,
     "configurePresets": [
-        {
+        {{
             "name": "vcpkg-base",
             "hidden": true,
             "generator": "Ninja",
-            "binaryDir": "${sourceDir}/build/${presetName}",
-            "installDir": "${sourceDir}/install/${presetName}",
-            "cacheVariables": {
-                "CMAKE_TOOLCHAIN_FILE": "$env{VCPKG_ROOT}/scripts/buildsystems/vcpkg.cmake",
+            "binaryDir": "${{sourceDir}}/build/${{presetName}}",
+            "installDir": "${{sourceDir}}/install/${{presetName}}",
+            "cacheVariables": {{
+                "CMAKE_TOOLCHAIN_FILE": "$env{{VCPKG_ROOT}}/scripts/buildsystems/vcpkg.cmake",
                 "CMAKE_EXPORT_COMPILE_COMMANDS": "ON"
-            }
-        },
-        {
+            }}
+        }},
+        {{
             "name": "debug",
             "displayName": "Debug Build",
             "inherits": "vcpkg-base",
-            "cacheVariables": { "CMAKE_BUILD_TYPE": "Debug" }
-        },
-        {
+            "cacheVariables": {{ "CMAKE_BUILD_TYPE": "Debug" }}
+        }},
+        {{
             "name": "release",
             "displayName": "Release Build",
             "inherits": "vcpkg-base",
-            "cacheVariables": { "CMAKE_BUILD_TYPE": "Release" }
-        }
+            "cacheVariables": {{ "CMAKE_BUILD_TYPE": "Release" }}
+        }}{configure_presets}
     ],
     "buildPresets": [
-        { "name": "debug", "configurePreset": "debug" },
-        { "name": "release", "configurePreset": "release" }
+        {{ "name": "debug", "configurePreset": "debug" }},
+        {{ "name": "release", "configurePreset": "release" }}{build_presets}
     ],
     "testPresets": [
-      { "name": "debug", "configurePreset": "debug", "output": {"outputOnFailure": true}, "execution": {"noTestsAction": "error", "stopOnFailure": true} },
-      { "name": "release", "configurePreset": "release", "output": {"outputOnFailure": true}, "execution": {"noTestsAction": "error", "stopOnFailure": true} }
+      {{ "name": "debug", "configurePreset": "debug", "output": {{"outputOnFailure": true}}, "execution": {{"noTestsAction": "error", "stopOnFailure": true}} }},
+      {{ "name": "release", "configurePreset": "release", "output": {{"outputOnFailure": true}}, "execution": {{"noTestsAction": "error", "stopOnFailure": true}} }}{test_presets}
     ]
-}"#.to_string()
+}}"#
+    )
 }
 
-pub fn generate_cmakeuserpresets_content(vcpkg_root_path: &Path) -> String {
-    let vcpkg_root_json_escaped = vcpkg_root_path.to_string_lossy().replace('\\', "\\\\");
+/// Generates CMakeUserPresets.json with the machine-specific `dev` preset:
+/// the vcpkg checkout, the compiler and the build tool.
+pub fn generate_cmakeuserpresets_content(
+    vcpkg_root_path: &Path,
+    compiler: Option<&Compiler>,
+    ninja: Option<&Path>,
+) -> String {
+    let json_escape = |value: &str| value.replace('\\', "\\\\").replace('"', "\\\"");
+    let vcpkg_root_json_escaped = json_escape(&vcpkg_root_path.to_string_lossy());
+
+    let mut cache_variables = Vec::new();
+    if let Some(compiler) = compiler {
+        cache_variables.push(format!("\"CMAKE_C_COMPILER\": \"{}\"", json_escape(&compiler.c)));
+        cache_variables.push(format!("\"CMAKE_CXX_COMPILER\": \"{}\"", json_escape(&compiler.cxx)));
+    }
+    // Without Ninja the `generator` of the base preset is overridden.
+    let generator = match ninja {
+        Some(ninja) => {
+            cache_variables.push(format!(
+                "\"CMAKE_MAKE_PROGRAM\": \"{}\"",
+                json_escape(&ninja.to_string_lossy())
+            ));
+            String::new()
+        }
+        None => format!(
+            "\n            \"generator\": \"{}\",",
+            toolchain::fallback_generator()
+        ),
+    };
+    let cache_variables = if cache_variables.is_empty() {
+        String::new()
+    } else {
+        format!(
+            r#",
+            "cacheVariables": {{
+                {}
+            }}"#,
+            cache_variables.join(",\n                ")
+        )
+    };
+
     format!(
         r#"{{
     "version": 3,
@@ -93,33 +539,112 @@ pub fn generate_cmakeuserpresets_content(vcpkg_root_path: &Path) -> String {
         {{
             "name": "dev",
             "displayName": "Developer Default (Debug)",
-            "inherits": "debug",
+            "inherits": "debug",{}
             "environment": {{
-                "VCPKG_ROOT": "{}",
-                "CMAKE_MAKE_PROGRAM": "{}",
-                "CMAKE_C_COMPILER": "{}",
-                "CMAKE_CXX_COMPILER": "{}"
-            }}
+                "VCPKG_ROOT": "{}"
+            }}{}
         }}
     ],
     "buildPresets": [ {{ "name": "dev", "configurePreset": "dev" }} ],
     "testPresets": [ {{ "name": "dev", "configurePreset": "dev" }} ]
 }}"#,
-        vcpkg_root_json_escaped,
-        if cfg!(windows) {
-            "ninja.exe"
-        } else {
-            "ninja"
-        },
-        if cfg!(windows) {
-            "cl.exe"
-        } else {
-            "clang"
-        },
-        if cfg!(windows) {
-            "cl.exe"
-        } else {
-            "clang++"
-        }
+        generator, vcpkg_root_json_escaped, cache_variables
     )
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CMAKELISTS: &str = "project(app CXX)
+add_executable(app main.cc)
+
+# >>> rig dependencies >>>
+# Managed by `rig add` / `rig remove`.
+# vcpkg port: fmt
+find_package(fmt CONFIG REQUIRED)
+target_link_libraries(app PRIVATE fmt::fmt)
+# <<< rig dependencies <<<
+install(TARGETS app)
+";
+
+    fn entry(port: &str) -> DependencyEntry {
+        let target = LinkTarget::detect(CMAKELISTS).unwrap();
+        DependencyEntry::new(port, &CMakePackage::guess(port), &target)
+    }
+
+    #[test]
+    fn managed_block_round_trips() {
+        let managed = ManagedCMakeLists::parse(CMAKELISTS).unwrap().unwrap();
+        assert_eq!(managed.entries.len(), 1);
+        assert_eq!(managed.render(), CMAKELISTS);
+
+        let unterminated = CMAKELISTS.strip_suffix("install(TARGETS app)\n").unwrap().trim_end();
+        let managed = ManagedCMakeLists::parse(unterminated).unwrap().unwrap();
+        assert_eq!(managed.render(), unterminated);
+    }
+
+    #[test]
+    fn adds_and_removes_entries() {
+        let mut managed = ManagedCMakeLists::parse(CMAKELISTS).unwrap().unwrap();
+        managed.add(entry("spdlog"));
+        let added = managed.render();
+        assert!(added.contains(
+            "target_link_libraries(app PRIVATE fmt::fmt)\n# vcpkg port: spdlog\nfind_package(spdlog CONFIG REQUIRED)\ntarget_link_libraries(app PRIVATE spdlog::spdlog)\n# <<< rig dependencies <<<\n"
+        ));
+
+        // Adding the same port again is a no-op for `rig add`.
+        let reparsed = ManagedCMakeLists::parse(&added).unwrap().unwrap();
+        assert!(reparsed.contains("spdlog"));
+        assert_eq!(reparsed.render(), added);
+
+        let mut managed = reparsed;
+        assert!(managed.remove("spdlog"));
+        assert!(!managed.remove("spdlog"));
+        assert_eq!(managed.render(), CMAKELISTS);
+    }
+
+    #[test]
+    fn file_without_block_is_not_managed() {
+        assert!(ManagedCMakeLists::parse("project(app CXX)\n").unwrap().is_none());
+    }
+
+    #[test]
+    fn rejects_missing_end_marker() {
+        let content = CMAKELISTS.replace("# <<< rig dependencies <<<\n", "");
+        let error = ManagedCMakeLists::parse(&content).err().unwrap();
+        assert!(error.to_string().contains("no `# <<< rig dependencies <<<` line"), "{}", error);
+
+        let begin_only = "project(app CXX)\n# >>> rig dependencies >>>";
+        assert!(ManagedCMakeLists::parse(begin_only).is_err());
+    }
+
+    #[test]
+    fn rejects_duplicate_blocks() {
+        let twice = format!("{}\n{}", CMAKELISTS, CMAKELISTS);
+        let error = ManagedCMakeLists::parse(&twice).err().unwrap();
+        assert!(error.to_string().contains("more than one"), "{}", error);
+
+        let nested = CMAKELISTS.replace(
+            "# vcpkg port: fmt",
+            "# >>> rig dependencies >>>\n# vcpkg port: fmt",
+        );
+        assert!(ManagedCMakeLists::parse(&nested).is_err());
+    }
+
+    #[test]
+    fn find_dependency_drops_required() {
+        let package = CMakePackage {
+            find_package_args: "unofficial-sqlite3 CONFIG REQUIRED".to_string(),
+            targets: vec!["unofficial::sqlite3::sqlite3".to_string()],
+        };
+        let entry = DependencyEntry::find_dependency("sqlite3", &package);
+        assert_eq!(entry.lines, ["find_dependency(unofficial-sqlite3 CONFIG)"]);
+
+        let boost = DependencyEntry::find_dependency("boost", &CMakePackage {
+            find_package_args: "Boost REQUIRED COMPONENTS filesystem".to_string(),
+            targets: Vec::new(),
+        });
+        assert_eq!(boost.lines, ["find_dependency(Boost COMPONENTS filesystem)"]);
+    }
+}
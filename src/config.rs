@@ -1,18 +1,129 @@
 // src/config.rs
-use crate::vcpkg::{self, VcpkgPaths};
-use anyhow::Result;
-use std::path::PathBuf;
+use crate::manifest::VcpkgManifest;
+use crate::toolchain::Compiler;
+use crate::utils::{self, find_project_root_by_marker};
+use crate::vcpkg::{self, VcpkgPaths, VCPKG_JSON_FILENAME};
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
 
 // Filename constants related to project structure
 pub(crate) const MAIN_CPP_FILENAME: &str = "main.cc";
 pub(crate) const GITIGNORE_FILENAME: &str = ".gitignore";
+pub(crate) const CLANG_FORMAT_FILENAME: &str = ".clang-format";
+pub(crate) const CLANG_TIDY_FILENAME: &str = ".clang-tidy";
+pub(crate) const RIG_TOML_FILENAME: &str = "rig.toml";
+pub(crate) const INCLUDE_DIRNAME: &str = "include";
+pub(crate) const SRC_DIRNAME: &str = "src";
+pub(crate) const CMAKE_MODULES_DIRNAME: &str = "cmake";
+
+// Defaults used when neither the CLI nor rig.toml say otherwise
+pub(crate) const DEFAULT_PRESET: &str = "dev";
+pub(crate) const DEFAULT_CPP_STANDARD: &str = "17";
+
+/// The kind of library generated by `rig new --lib`.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LibraryKind {
+    Static,
+    Shared,
+    HeaderOnly,
+}
+
+impl LibraryKind {
+    /// The library type keyword used in `add_library`.
+    pub fn cmake_keyword(self) -> &'static str {
+        match self {
+            LibraryKind::Static => "STATIC",
+            LibraryKind::Shared => "SHARED",
+            LibraryKind::HeaderOnly => "INTERFACE",
+        }
+    }
+}
+
+/// Contents of `rig.toml`. Every field is optional; missing values fall back to defaults.
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct RigToml {
+    pub project: ProjectSection,
+    pub build: BuildSection,
+    pub toolchain: ToolchainSection,
+    pub vcpkg: VcpkgSection,
+    pub run: RunSection,
+}
+
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct ProjectSection {
+    pub name: Option<String>,
+    /// `executable`, or the library kind: `static`, `shared` or `header-only`.
+    pub kind: Option<String>,
+    /// The project's CMake targets; the first executable among them is what
+    /// `rig run` runs when no `--bin` is given.
+    pub targets: Vec<String>,
+}
+
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct BuildSection {
+    pub preset: Option<String>,
+    pub cpp_standard: Option<String>,
+}
+
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct ToolchainSection {
+    pub compiler: Option<String>,
+}
+
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct VcpkgSection {
+    /// Used when neither --vcpkg-root nor VCPKG_ROOT is given.
+    pub root: Option<String>,
+}
+
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct RunSection {
+    /// Arguments passed to the executable when none are given on the command line.
+    pub args: Vec<String>,
+}
+
+impl RigToml {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content =
+            fs::read_to_string(path).with_context(|| format!("Failed to read {:?}", path))?;
+        toml::from_str(&content).with_context(|| format!("Failed to parse {:?}", path))
+    }
+}
+
+/// Finds the root of the rig project containing the current directory (marked by rig.toml).
+pub fn find_project_root() -> Result<PathBuf> {
+    find_project_root_by_marker(RIG_TOML_FILENAME).context(
+        "Failed to find project root. Are you in a rig project? Run `rig init` to adopt an existing project.",
+    )
+}
 
 pub struct ProjectConfig {
     pub project_name: String,
     pub project_path: PathBuf,
-    pub vcpkg_paths: VcpkgPaths,
+    /// The located checkout, or why it could not be located.
+    vcpkg_paths: std::result::Result<VcpkgPaths, String>,
+    /// The `[vcpkg] root` of rig.toml; for `rig new` and `rig init`, the
+    /// absolute `--vcpkg-root` if one was given.
+    pub vcpkg_root: Option<String>,
     pub dependencies: Vec<String>,
     pub cpp_standard: String,
+    /// `Some` when the project is a library rather than an executable.
+    pub library_kind: Option<LibraryKind>,
+    /// The CMake preset commands use unless one is given on the command line.
+    pub preset: String,
+    /// Compiler name from `rig toolchain list`; `None` leaves the choice to CMake.
+    pub compiler: Option<String>,
+    /// Executables `rig run` prefers when `--bin` is not given; only set by the user.
+    pub targets: Vec<String>,
+    pub run_args: Vec<String>,
     // Add other common paths or settings here if needed
     pub main_cpp_file: String, // e.g. "main.cc"
 }
@@ -23,17 +134,268 @@ impl ProjectConfig {
         vcpkg_root_override: Option<String>,
         dependencies: Vec<String>,
         cpp_standard: String,
+        library_kind: Option<LibraryKind>,
+        compiler: Option<&Compiler>,
     ) -> Result<Self> {
-        let vcpkg_paths = vcpkg::locate_and_verify_vcpkg(vcpkg_root_override)?;
+        let root_given = vcpkg_root_override.is_some();
+        let vcpkg_paths = vcpkg::locate_and_verify_vcpkg(vcpkg_root_override, None)?;
+        let vcpkg_root = root_given.then(|| vcpkg_paths.root.display().to_string());
         let project_path = PathBuf::from(&project_name);
+        let compiler = compiler.map(|c| c.name.clone());
 
         Ok(Self {
+            targets: Vec::new(),
             project_name,
             project_path,
-            vcpkg_paths,
+            vcpkg_paths: Ok(vcpkg_paths),
+            vcpkg_root,
             dependencies,
             cpp_standard,
+            library_kind,
+            preset: DEFAULT_PRESET.to_string(),
+            compiler,
+            run_args: Vec::new(),
+            main_cpp_file: MAIN_CPP_FILENAME.to_string(),
+        })
+    }
+
+    /// Builds a config for a project that already lives in `project_path`
+    /// (used by `rig init`). The project name is taken from the directory name.
+    pub fn for_existing_directory(
+        project_path: PathBuf,
+        vcpkg_root_override: Option<String>,
+        dependencies: Vec<String>,
+        cpp_standard: String,
+        compiler: Option<&Compiler>,
+    ) -> Result<Self> {
+        let root_given = vcpkg_root_override.is_some();
+        let vcpkg_paths = vcpkg::locate_and_verify_vcpkg(vcpkg_root_override, None)?;
+        let vcpkg_root = root_given.then(|| vcpkg_paths.root.display().to_string());
+        let project_name = project_path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .map(String::from)
+            .with_context(|| format!("Failed to determine project name from {:?}", project_path))?;
+        let compiler = compiler.map(|c| c.name.clone());
+
+        Ok(Self {
+            targets: Vec::new(),
+            project_name,
+            project_path,
+            vcpkg_paths: Ok(vcpkg_paths),
+            vcpkg_root,
+            dependencies,
+            cpp_standard,
+            library_kind: None,
+            preset: DEFAULT_PRESET.to_string(),
+            compiler,
+            run_args: Vec::new(),
+            main_cpp_file: MAIN_CPP_FILENAME.to_string(),
+        })
+    }
+
+    /// Loads the configuration of the project containing the current directory
+    /// from its rig.toml. A preset given on the command line overrides rig.toml.
+    pub fn load(
+        preset_override: Option<String>,
+        vcpkg_root_override: Option<String>,
+    ) -> Result<Self> {
+        let project_path = find_project_root()?;
+        let rig_toml = RigToml::load(&project_path.join(RIG_TOML_FILENAME))?;
+
+        let manifest_path = project_path.join(VCPKG_JSON_FILENAME);
+        let manifest = if manifest_path.exists() {
+            Some(VcpkgManifest::load(&manifest_path)?)
+        } else {
+            None
+        };
+
+        // rig.toml name, then the vcpkg.json name, then the directory name
+        let manifest_name = manifest
+            .as_ref()
+            .and_then(|m| m.name.clone())
+            .filter(|n| !n.is_empty());
+        let project_name = match rig_toml.project.name.or(manifest_name) {
+            Some(name) => name,
+            None => project_path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .map(String::from)
+                .with_context(|| {
+                    format!("Failed to determine project name from {:?}", project_path)
+                })?,
+        };
+        let library_kind = match rig_toml.project.kind.as_deref() {
+            None | Some("executable") => None,
+            Some(kind) => Some(
+                <LibraryKind as clap::ValueEnum>::from_str(kind, true).map_err(|_| {
+                    anyhow!(
+                        "Invalid project kind '{}' in {} (expected executable, static, shared or header-only)",
+                        kind,
+                        RIG_TOML_FILENAME
+                    )
+                })?,
+            ),
+        };
+
+        let dependencies = manifest
+            .map(|m| m.dependencies().iter().map(|dep| dep.name().to_string()).collect())
+            .unwrap_or_default();
+
+        // Commands that never touch vcpkg must keep working without it, so a
+        // missing checkout is only reported when `vcpkg()` is called.
+        let vcpkg_paths =
+            vcpkg::locate_and_verify_vcpkg(vcpkg_root_override, rig_toml.vcpkg.root.as_deref())
+                .map_err(|e| format!("{:#}", e));
+
+        Ok(Self {
+            targets: rig_toml.project.targets,
+            project_name,
+            project_path,
+            vcpkg_paths,
+            vcpkg_root: rig_toml.vcpkg.root,
+            dependencies,
+            cpp_standard: rig_toml
+                .build
+                .cpp_standard
+                .unwrap_or_else(|| DEFAULT_CPP_STANDARD.to_string()),
+            library_kind,
+            preset: preset_override
+                .or(rig_toml.build.preset)
+                .unwrap_or_else(|| DEFAULT_PRESET.to_string()),
+            compiler: rig_toml.toolchain.compiler,
+            run_args: rig_toml.run.args,
             main_cpp_file: MAIN_CPP_FILENAME.to_string(),
         })
     }
-}
\ No newline at end of file
+
+    /// The located vcpkg checkout, or an error explaining how to provide one.
+    pub fn vcpkg(&self) -> Result<&VcpkgPaths> {
+        self.vcpkg_paths.as_ref().map_err(|reason| {
+            anyhow!(
+                "vcpkg not found: {}. Pass --vcpkg-root, set VCPKG_ROOT, or set `root` in the [vcpkg] section of {}.",
+                reason.trim_end_matches('.'),
+                RIG_TOML_FILENAME
+            )
+        })
+    }
+
+    /// Name of the project as a C++ identifier, used for namespaces.
+    pub fn cpp_identifier(&self) -> String {
+        self.project_name.replace(['-', '.'], "_")
+    }
+
+    fn kind_name(&self) -> &'static str {
+        match self.library_kind {
+            None => "executable",
+            Some(LibraryKind::Static) => "static",
+            Some(LibraryKind::Shared) => "shared",
+            Some(LibraryKind::HeaderOnly) => "header-only",
+        }
+    }
+}
+
+/// Sets `key = value` (a string) in `[section]` of the rig.toml at `path`,
+/// editing the text in place so comments and layout are kept.
+pub fn set_rig_toml_value(path: &Path, section: &str, key: &str, value: &str) -> Result<()> {
+    let content =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {:?}", path))?;
+    let assignment = format!("{} = {}", key, toml_string(value));
+    let header = format!("[{}]", section);
+
+    let mut lines: Vec<String> = content.lines().map(String::from).collect();
+    let is_key_line = |line: &str| {
+        let line = line.trim_start().trim_start_matches('#').trim_start();
+        line.strip_prefix(key)
+            .is_some_and(|rest| rest.trim_start().starts_with('='))
+    };
+    match lines.iter().position(|line| line.trim() == header) {
+        Some(start) => {
+            let end = lines[start + 1..]
+                .iter()
+                .position(|line| line.trim_start().starts_with('['))
+                .map(|offset| start + 1 + offset)
+                .unwrap_or(lines.len());
+            // Prefer an active assignment over a commented-out one.
+            let existing = (start + 1..end)
+                .find(|&i| !lines[i].trim_start().starts_with('#') && is_key_line(&lines[i]))
+                .or_else(|| (start + 1..end).find(|&i| is_key_line(&lines[i])));
+            match existing {
+                Some(i) => lines[i] = assignment,
+                None => lines.insert(start + 1, assignment),
+            }
+        }
+        None => {
+            if lines.last().is_some_and(|line| !line.trim().is_empty()) {
+                lines.push(String::new());
+            }
+            lines.push(header);
+            lines.push(assignment);
+        }
+    }
+
+    let mut updated = lines.join("\n");
+    updated.push('\n');
+    toml::from_str::<RigToml>(&updated)
+        .with_context(|| format!("Updating {:?} would make it invalid", path))?;
+    utils::write(path, updated).with_context(|| format!("Failed to write {:?}", path))
+}
+
+// A TOML basic string; Rust's `{:?}` escapes are not all valid TOML.
+fn toml_string(value: &str) -> String {
+    toml::Value::String(value.to_string()).to_string()
+}
+
+fn toml_string_array(values: &[String]) -> String {
+    toml::Value::Array(values.iter().cloned().map(toml::Value::String).collect()).to_string()
+}
+
+/// Generates the rig.toml written by `rig new` and `rig init`.
+pub fn generate_rig_toml_content(config: &ProjectConfig) -> String {
+    format!(
+        r#"# Project settings for rig. Command-line flags override these values.
+
+[project]
+name = {name}
+kind = "{kind}"
+{targets}
+
+[build]
+preset = {preset}
+cpp-standard = {cpp_standard}
+
+[toolchain]
+# Change with `rig toolchain use <name>`; see `rig toolchain list`.
+{compiler}
+
+[vcpkg]
+# Used when neither --vcpkg-root nor VCPKG_ROOT is set.
+{vcpkg_root}
+
+[run]
+# Default arguments for `rig run` when none are given after `--`.
+args = {run_args}
+"#,
+        name = toml_string(&config.project_name),
+        kind = config.kind_name(),
+        targets = if config.targets.is_empty() {
+            format!(
+                "# Executables `rig run` picks without --bin, in order of preference.\n# targets = {}",
+                toml_string_array(std::slice::from_ref(&config.project_name))
+            )
+        } else {
+            format!("targets = {}", toml_string_array(&config.targets))
+        },
+        preset = toml_string(&config.preset),
+        cpp_standard = toml_string(&config.cpp_standard),
+        compiler = match &config.compiler {
+            Some(compiler) => format!("compiler = {}", toml_string(compiler)),
+            None => "# compiler = \"gcc\"".to_string(),
+        },
+        vcpkg_root = match &config.vcpkg_root {
+            Some(root) => format!("root = {}", toml_string(root)),
+            None => "# root = \"/path/to/vcpkg\"".to_string(),
+        },
+        run_args = toml_string_array(&config.run_args),
+    )
+}
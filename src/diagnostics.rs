@@ -0,0 +1,506 @@
+// src/diagnostics.rs
+//! Parses GCC and Clang diagnostics out of build output.
+//!
+//! Both compilers print diagnostics as `file:line:column: severity: message`,
+//! optionally followed by `[-Wflag]`, with `note:` lines attached to the
+//! diagnostic before them. With `-fdiagnostics-parseable-fixits`, fix-it hints
+//! follow as `fix-it:"file":{l:c-l:c}:"replacement"` lines. clang-tidy uses the
+//! same format, with the check name in place of the warning flag.
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::fmt;
+
+/// How `rig build` reports compiler diagnostics.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MessageFormat {
+    /// The build output as is, followed by a summary line
+    #[default]
+    Human,
+    /// One JSON object per diagnostic on stdout; the build output goes to stderr
+    Json,
+}
+
+#[derive(clap::ValueEnum, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Note => "note",
+        };
+        f.write_str(name)
+    }
+}
+
+impl Severity {
+    /// Whether a diagnostic of this severity is at least as severe as `threshold`.
+    pub fn at_least(self, threshold: Severity) -> bool {
+        let rank = |severity| match severity {
+            Severity::Note => 0,
+            Severity::Warning => 1,
+            Severity::Error => 2,
+        };
+        rank(self) >= rank(threshold)
+    }
+}
+
+/// A suggested replacement of the text between two positions.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct FixIt {
+    pub file: String,
+    pub start_line: u32,
+    pub start_column: u32,
+    pub end_line: u32,
+    pub end_column: u32,
+    pub replacement: String,
+}
+
+/// A compiler diagnostic. Linker and driver messages have no location.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+    pub severity: Severity,
+    pub message: String,
+    /// The warning flag or clang-tidy check behind the diagnostic, e.g.
+    /// `-Wunused-variable` or `bugprone-use-after-move`.
+    pub option: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<Diagnostic>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fixits: Vec<FixIt>,
+}
+
+impl Diagnostic {
+    /// Whether both report the same problem at the same place, ignoring notes
+    /// and fix-its that only one compiler invocation printed.
+    pub fn same_as(&self, other: &Diagnostic) -> bool {
+        self.file == other.file
+            && self.line == other.line
+            && self.column == other.column
+            && self.severity == other.severity
+            && self.message == other.message
+    }
+}
+
+// Removes the ANSI color sequences compilers emit with -fdiagnostics-color=always.
+fn strip_ansi(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c == '\u{1b}' {
+            // Skip `ESC [ ... <letter>`.
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+// clang-tidy check names: `bugprone-use-after-move`, `clang-analyzer-core.NullDereference`.
+fn is_check_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_lowercase())
+        && name.contains('-')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.' || c == '_')
+}
+
+// The `-W` flag or check name in a trailing `[...]`: `[-Wunused]`,
+// `[-Werror=unused]`, `[-Werror,-Wunused]`, `[bugprone-branch-clone]`.
+fn split_option(message: &str) -> (String, Option<String>) {
+    let Some(open) = message.rfind(" [") else {
+        return (message.to_string(), None);
+    };
+    let Some(inner) = message[open + 2..].strip_suffix(']') else {
+        return (message.to_string(), None);
+    };
+    let option = inner
+        .split(',')
+        .map(str::trim)
+        .filter(|flag| (flag.starts_with("-W") && *flag != "-Werror") || is_check_name(flag))
+        .map(|flag| match flag.strip_prefix("-Werror=") {
+            Some(name) => format!("-W{}", name),
+            // GCC spells flags that take a level as `-Wformat=`.
+            None => flag.trim_end_matches('=').to_string(),
+        })
+        .next();
+    match option {
+        Some(option) => (message[..open].to_string(), Some(option)),
+        None => (message.to_string(), None),
+    }
+}
+
+// `file:line:column`, `file:line` or a bare program name such as `collect2`.
+fn parse_location(location: &str) -> (Option<String>, Option<u32>, Option<u32>) {
+    let mut parts = location.rsplitn(3, ':');
+    let last = parts.next().unwrap_or_default();
+    let middle = parts.next();
+    let first = parts.next();
+    match (first, middle.map(str::parse::<u32>), last.parse::<u32>()) {
+        (Some(file), Some(Ok(line)), Ok(column)) => {
+            (Some(file.to_string()), Some(line), Some(column))
+        }
+        _ => match (location.rsplit_once(':'), last.parse::<u32>()) {
+            (Some((file, _)), Ok(line)) => (Some(file.to_string()), Some(line), None),
+            _ => (None, None, None),
+        },
+    }
+}
+
+fn parse_diagnostic_line(line: &str) -> Option<Diagnostic> {
+    const MARKERS: [(&str, Severity); 4] = [
+        (": fatal error: ", Severity::Error),
+        (": error: ", Severity::Error),
+        (": warning: ", Severity::Warning),
+        (": note: ", Severity::Note),
+    ];
+    let (position, marker, severity) = MARKERS
+        .iter()
+        .filter_map(|(marker, severity)| line.find(marker).map(|pos| (pos, *marker, *severity)))
+        .min_by_key(|(pos, _, _)| *pos)?;
+    let location = &line[..position];
+    if location.is_empty() || location.contains(' ') && !location.contains(':') {
+        return None;
+    }
+    let (file, line_number, column) = parse_location(location);
+    let (message, option) = split_option(line[position + marker.len()..].trim());
+    Some(Diagnostic {
+        file,
+        line: line_number,
+        column,
+        severity,
+        message,
+        option,
+        notes: Vec::new(),
+        fixits: Vec::new(),
+    })
+}
+
+// Undoes the C-style escaping of fix-it strings: `\\`, `\"`, `\n`, `\t` and
+// the octal `\ooo` GCC and Clang use for other non-printable bytes.
+fn unescape(text: &str) -> String {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut rest = text.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        rest = tail;
+        if byte != b'\\' {
+            bytes.push(byte);
+            continue;
+        }
+        let Some((&escaped, tail)) = rest.split_first() else {
+            bytes.push(byte);
+            break;
+        };
+        rest = tail;
+        match escaped {
+            b'n' => bytes.push(b'\n'),
+            b't' => bytes.push(b'\t'),
+            b'0'..=b'7' => {
+                let mut value = u32::from(escaped - b'0');
+                for _ in 0..2 {
+                    match rest.split_first() {
+                        Some((&digit, tail)) if (b'0'..=b'7').contains(&digit) => {
+                            value = value * 8 + u32::from(digit - b'0');
+                            rest = tail;
+                        }
+                        _ => break,
+                    }
+                }
+                bytes.push(value as u8);
+            }
+            other => bytes.push(other),
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+// `fix-it:"file":{12:5-12:9}:"replacement"`
+fn parse_fixit(line: &str) -> Option<FixIt> {
+    let rest = line.strip_prefix("fix-it:\"")?;
+    let (file, rest) = rest.split_once("\":{")?;
+    let (range, replacement) = rest.split_once("}:")?;
+    let (start, end) = range.split_once('-')?;
+    let (start_line, start_column) = start.split_once(':')?;
+    let (end_line, end_column) = end.split_once(':')?;
+    let replacement = replacement.strip_prefix('"')?.strip_suffix('"')?;
+    Some(FixIt {
+        file: unescape(file),
+        start_line: start_line.parse().ok()?,
+        start_column: start_column.parse().ok()?,
+        end_line: end_line.parse().ok()?,
+        end_column: end_column.parse().ok()?,
+        replacement: unescape(replacement),
+    })
+}
+
+/// Turns build output, fed line by line, into diagnostics.
+#[derive(Default)]
+pub struct DiagnosticParser {
+    current: Option<Diagnostic>,
+}
+
+impl DiagnosticParser {
+    /// Feeds one line of output. Returns the previous diagnostic once it is
+    /// complete, i.e. when the next one starts.
+    pub fn feed(&mut self, line: &str) -> Option<Diagnostic> {
+        let line = strip_ansi(line);
+        if let Some(fixit) = parse_fixit(&line) {
+            if let Some(current) = &mut self.current {
+                current.fixits.push(fixit);
+            }
+            return None;
+        }
+        let diagnostic = parse_diagnostic_line(&line)?;
+        if diagnostic.severity == Severity::Note
+            && let Some(current) = &mut self.current
+        {
+            current.notes.push(diagnostic);
+            return None;
+        }
+        self.current.replace(diagnostic)
+    }
+
+    /// Returns the last diagnostic at the end of the output.
+    pub fn finish(&mut self) -> Option<Diagnostic> {
+        self.current.take()
+    }
+}
+
+/// Error and warning counts of a build.
+#[derive(Default)]
+pub struct DiagnosticSummary {
+    pub errors: usize,
+    pub warnings: usize,
+    files: BTreeSet<String>,
+}
+
+impl DiagnosticSummary {
+    pub fn record(&mut self, diagnostic: &Diagnostic) {
+        match diagnostic.severity {
+            Severity::Error => self.errors += 1,
+            Severity::Warning => self.warnings += 1,
+            Severity::Note => return,
+        }
+        if let Some(file) = &diagnostic.file {
+            self.files.insert(file.clone());
+        }
+    }
+
+    pub fn file_count(&self) -> usize {
+        self.files.len()
+    }
+}
+
+impl fmt::Display for DiagnosticSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let plural = |count: usize, word: &str| {
+            format!("{} {}{}", count, word, if count == 1 { "" } else { "s" })
+        };
+        write!(
+            f,
+            "{}, {} in {}",
+            plural(self.errors, "error"),
+            plural(self.warnings, "warning"),
+            plural(self.file_count(), "file")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(output: &str) -> Vec<Diagnostic> {
+        let mut parser = DiagnosticParser::default();
+        let mut diagnostics: Vec<Diagnostic> = output.lines().filter_map(|l| parser.feed(l)).collect();
+        diagnostics.extend(parser.finish());
+        diagnostics
+    }
+
+    // (file, line, column, severity, message, option) of each diagnostic.
+    type Row<'a> = (Option<&'a str>, Option<u32>, Option<u32>, Severity, &'a str, Option<&'a str>);
+
+    fn rows(diagnostics: &[Diagnostic]) -> Vec<Row<'_>> {
+        diagnostics
+            .iter()
+            .map(|d| {
+                (
+                    d.file.as_deref(),
+                    d.line,
+                    d.column,
+                    d.severity,
+                    d.message.as_str(),
+                    d.option.as_deref(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parses_compiler_output() {
+        use Severity::*;
+        let cases: &[(&str, &[Row])] = &[
+            // GCC
+            (
+                "main.cc: In function 'int main()':\n\
+                 main.cc:4:9: warning: unused variable 'x' [-Wunused-variable]\n\
+                 \x20   4 |     int x = 0;\n\
+                 \x20     |         ^",
+                &[(Some("main.cc"), Some(4), Some(9), Warning, "unused variable 'x'", Some("-Wunused-variable"))],
+            ),
+            (
+                "main.cc:4:9: error: unused variable 'x' [-Werror=unused-variable]\n\
+                 cc1plus: all warnings being treated as errors",
+                &[(Some("main.cc"), Some(4), Some(9), Error, "unused variable 'x'", Some("-Wunused-variable"))],
+            ),
+            (
+                "main.cc:7:15: warning: format '%d' expects argument of type 'int' [-Wformat=]",
+                &[(Some("main.cc"), Some(7), Some(15), Warning, "format '%d' expects argument of type 'int'", Some("-Wformat"))],
+            ),
+            (
+                "main.cc:1:10: fatal error: fmt/core.h: No such file or directory\n\
+                 compilation terminated.",
+                &[(Some("main.cc"), Some(1), Some(10), Error, "fmt/core.h: No such file or directory", None)],
+            ),
+            (
+                "/usr/bin/ld: main.o: in function `main':\n\
+                 main.cc:(.text+0x9): undefined reference to `foo()'\n\
+                 collect2: error: ld returned 1 exit status",
+                &[(None, None, None, Error, "ld returned 1 exit status", None)],
+            ),
+            // Clang
+            (
+                "main.cc:4:9: warning: unused variable 'x' [-Wunused-variable]",
+                &[(Some("main.cc"), Some(4), Some(9), Warning, "unused variable 'x'", Some("-Wunused-variable"))],
+            ),
+            (
+                "main.cc:4:9: error: unused variable 'x' [-Werror,-Wunused-variable]\n\
+                 1 error generated.",
+                &[(Some("main.cc"), Some(4), Some(9), Error, "unused variable 'x'", Some("-Wunused-variable"))],
+            ),
+            (
+                "main.cc:5:3: error: no matching function for call to 'f': candidate expects 2 arguments [bugprone-argument-comment]",
+                &[(Some("main.cc"), Some(5), Some(3), Error, "no matching function for call to 'f': candidate expects 2 arguments", Some("bugprone-argument-comment"))],
+            ),
+            (
+                "clang++: error: linker command failed with exit code 1 (use -v to see invocation)",
+                &[(None, None, None, Error, "linker command failed with exit code 1 (use -v to see invocation)", None)],
+            ),
+            (
+                "main.cc:9:1: error: expected ';' after class [x]",
+                &[(Some("main.cc"), Some(9), Some(1), Error, "expected ';' after class [x]", None)],
+            ),
+            // Windows paths
+            (
+                "C:\\src\\app\\main.cc:12:5: warning: unused parameter 'argc' [-Wunused-parameter]",
+                &[(Some("C:\\src\\app\\main.cc"), Some(12), Some(5), Warning, "unused parameter 'argc'", Some("-Wunused-parameter"))],
+            ),
+            (
+                "C:/src/app/main.cc:12: error: expected declaration",
+                &[(Some("C:/src/app/main.cc"), Some(12), None, Error, "expected declaration", None)],
+            ),
+            (
+                "/home/me/My Project/main.cc:3:1: error: unknown type name 'strin'",
+                &[(Some("/home/me/My Project/main.cc"), Some(3), Some(1), Error, "unknown type name 'strin'", None)],
+            ),
+            // Not diagnostics
+            ("[ 50%] Building CXX object CMakeFiles/app.dir/main.cc.o\nninja: build stopped: subcommand failed.", &[]),
+        ];
+        for (output, expected) in cases {
+            assert_eq!(rows(&parse(output)), *expected, "{}", output);
+        }
+    }
+
+    #[test]
+    fn attaches_notes_to_previous_diagnostic() {
+        let diagnostics = parse(
+            "main.cc:8:5: error: use of deleted function 'Foo::Foo(const Foo&)'\n\
+             \x20   8 |     Foo b = a;\n\
+             main.cc:3:5: note: declared here\n\
+             main.cc:12:1: warning: control reaches end of non-void function [-Wreturn-type]\n\
+             main.cc:20:3: note: in instantiation of function template specialization 'f<int>' requested here",
+        );
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(
+            rows(&diagnostics[0].notes),
+            [(Some("main.cc"), Some(3), Some(5), Severity::Note, "declared here", None)]
+        );
+        assert_eq!(diagnostics[1].notes.len(), 1);
+        assert_eq!(diagnostics[1].notes[0].line, Some(20));
+    }
+
+    #[test]
+    fn locates_diagnostics_in_included_headers() {
+        let gcc = parse(
+            "In file included from /src/app/main.cc:1:\n\
+             /src/app/util.h:3:12: warning: 'helper' defined but not used [-Wunused-function]",
+        );
+        let clang = parse(
+            "In file included from /src/app/main.cc:1:\n\
+             In file included from /src/app/util.h:2:\n\
+             /src/app/detail.h:7:1: error: unknown type name 'strng'",
+        );
+        assert_eq!(
+            rows(&gcc),
+            [(Some("/src/app/util.h"), Some(3), Some(12), Severity::Warning, "'helper' defined but not used", Some("-Wunused-function"))]
+        );
+        assert_eq!(
+            rows(&clang),
+            [(Some("/src/app/detail.h"), Some(7), Some(1), Severity::Error, "unknown type name 'strng'", None)]
+        );
+    }
+
+    #[test]
+    fn attaches_fixits() {
+        let diagnostics = parse(
+            "main.cc:5:14: error: expected ';' after expression\n\
+             \x20   5 |   int x = 1\n\
+             fix-it:\"main.cc\":{5:14-5:14}:\";\"\n\
+             C:\\src\\main.cc:2:10: warning: use of 'NULL' [modernize-use-nullptr]\n\
+             fix-it:\"C:\\\\src\\\\main.cc\":{2:10-2:14}:\"nullptr\"\n\
+             fix-it:\"main.cc\":{3:1-4:1}:\"say(\\\"hi\\\")\\n\"\n\
+             fix-it:\"main.cc\":{6:1-6:1}:\"a\\\\nb\\303\\251\"",
+        );
+        assert_eq!(
+            diagnostics[0].fixits,
+            [FixIt {
+                file: "main.cc".to_string(),
+                start_line: 5,
+                start_column: 14,
+                end_line: 5,
+                end_column: 14,
+                replacement: ";".to_string(),
+            }]
+        );
+        let fixits = &diagnostics[1].fixits;
+        assert_eq!(fixits.len(), 3);
+        assert_eq!((fixits[0].file.as_str(), fixits[0].replacement.as_str()), ("C:\\src\\main.cc", "nullptr"));
+        assert_eq!((fixits[1].end_line, fixits[1].replacement.as_str()), (4, "say(\"hi\")\n"));
+        assert_eq!(fixits[2].replacement, "a\\nb\u{e9}");
+    }
+
+    #[test]
+    fn strips_colors() {
+        let diagnostics = parse(
+            "\u{1b}[01m\u{1b}[Kmain.cc:4:9:\u{1b}[m\u{1b}[K \u{1b}[01;35m\u{1b}[Kwarning: \u{1b}[m\u{1b}[Kunused variable '\u{1b}[01m\u{1b}[Kx\u{1b}[m\u{1b}[K' [\u{1b}[01;35m\u{1b}[K-Wunused-variable\u{1b}[m\u{1b}[K]",
+        );
+        assert_eq!(
+            rows(&diagnostics),
+            [(Some("main.cc"), Some(4), Some(9), Severity::Warning, "unused variable 'x'", Some("-Wunused-variable"))]
+        );
+    }
+}
@@ -0,0 +1,335 @@
+// src/elf.rs
+//! Reads the shared-library dependencies of ELF executables and libraries.
+//!
+//! The `DT_NEEDED` entries live in the dynamic section, which the `PT_DYNAMIC`
+//! program header points at. Their names are offsets into the string table,
+//! whose `DT_STRTAB` entry is a virtual address that the `PT_LOAD` segments map
+//! back to a file offset. 32- and 64-bit files of either byte order are read.
+use anyhow::{bail, Context, Result};
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::Path;
+
+const ELF_MAGIC: &[u8; 4] = b"\x7fELF";
+const PT_LOAD: u32 = 1;
+const PT_DYNAMIC: u32 = 2;
+const DT_NULL: u64 = 0;
+const DT_NEEDED: u64 = 1;
+const DT_STRTAB: u64 = 5;
+
+struct Reader<'a> {
+    data: &'a [u8],
+    is_64: bool,
+    big_endian: bool,
+}
+
+impl Reader<'_> {
+    fn bytes<const N: usize>(&self, offset: u64) -> Result<[u8; N]> {
+        let start = usize::try_from(offset).context("Offset out of range")?;
+        self.data
+            .get(start..start.saturating_add(N))
+            .and_then(|bytes| bytes.try_into().ok())
+            .context("Truncated ELF file")
+    }
+
+    fn u16(&self, offset: u64) -> Result<u16> {
+        let bytes = self.bytes(offset)?;
+        Ok(if self.big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        })
+    }
+
+    fn u32(&self, offset: u64) -> Result<u32> {
+        let bytes = self.bytes(offset)?;
+        Ok(if self.big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        })
+    }
+
+    fn u64(&self, offset: u64) -> Result<u64> {
+        let bytes = self.bytes(offset)?;
+        Ok(if self.big_endian {
+            u64::from_be_bytes(bytes)
+        } else {
+            u64::from_le_bytes(bytes)
+        })
+    }
+
+    // Addresses, offsets, sizes and dynamic entries: 4 bytes in 32-bit files, 8 in 64-bit ones.
+    fn word(&self, offset: u64) -> Result<u64> {
+        if self.is_64 {
+            self.u64(offset)
+        } else {
+            self.u32(offset).map(u64::from)
+        }
+    }
+
+    fn string(&self, offset: u64) -> Result<String> {
+        let start = usize::try_from(offset).context("Offset out of range")?;
+        let rest = self.data.get(start..).context("String outside of the file")?;
+        let end = rest
+            .iter()
+            .position(|byte| *byte == 0)
+            .context("Unterminated string")?;
+        Ok(String::from_utf8_lossy(&rest[..end]).into_owned())
+    }
+}
+
+struct Segment {
+    kind: u32,
+    offset: u64,
+    vaddr: u64,
+    filesz: u64,
+}
+
+fn parse_needed(data: &[u8]) -> Result<Option<Vec<String>>> {
+    if !data.starts_with(ELF_MAGIC) {
+        bail!("Not an ELF file");
+    }
+    let is_64 = match data.get(4) {
+        Some(1) => false,
+        Some(2) => true,
+        _ => bail!("Unknown ELF class"),
+    };
+    let big_endian = match data.get(5) {
+        Some(1) => false,
+        Some(2) => true,
+        _ => bail!("Unknown ELF byte order"),
+    };
+    let reader = Reader {
+        data,
+        is_64,
+        big_endian,
+    };
+
+    let (phoff, phentsize, phnum) = if is_64 {
+        (reader.u64(0x20)?, reader.u16(0x36)?, reader.u16(0x38)?)
+    } else {
+        (u64::from(reader.u32(0x1c)?), reader.u16(0x2a)?, reader.u16(0x2c)?)
+    };
+    let mut segments = Vec::new();
+    for index in 0..u64::from(phnum) {
+        let base = phoff + index * u64::from(phentsize);
+        // Elf64_Phdr puts p_flags right after p_type; Elf32_Phdr has it near the end.
+        let segment = if is_64 {
+            Segment {
+                kind: reader.u32(base)?,
+                offset: reader.u64(base + 8)?,
+                vaddr: reader.u64(base + 16)?,
+                filesz: reader.u64(base + 32)?,
+            }
+        } else {
+            Segment {
+                kind: reader.u32(base)?,
+                offset: u64::from(reader.u32(base + 4)?),
+                vaddr: u64::from(reader.u32(base + 8)?),
+                filesz: u64::from(reader.u32(base + 16)?),
+            }
+        };
+        segments.push(segment);
+    }
+    // Statically linked files have no dynamic section.
+    let Some(dynamic) = segments.iter().find(|s| s.kind == PT_DYNAMIC) else {
+        return Ok(None);
+    };
+
+    let word_size = if is_64 { 8 } else { 4 };
+    let mut needed_offsets = Vec::new();
+    let mut strtab = None;
+    for index in 0..dynamic.filesz / (2 * word_size) {
+        let base = dynamic.offset + index * 2 * word_size;
+        let tag = reader.word(base)?;
+        let value = reader.word(base + word_size)?;
+        match tag {
+            DT_NULL => break,
+            DT_NEEDED => needed_offsets.push(value),
+            DT_STRTAB => strtab = Some(value),
+            _ => {}
+        }
+    }
+    if needed_offsets.is_empty() {
+        return Ok(Some(Vec::new()));
+    }
+
+    let strtab = strtab.context("The dynamic section has no string table")?;
+    let strtab_offset = segments
+        .iter()
+        .filter(|s| s.kind == PT_LOAD)
+        .find(|s| s.vaddr <= strtab && strtab < s.vaddr + s.filesz)
+        .map(|s| strtab - s.vaddr + s.offset)
+        .context("The string table is outside of the loaded segments")?;
+    needed_offsets
+        .into_iter()
+        .map(|offset| reader.string(strtab_offset + offset))
+        .collect::<Result<Vec<_>>>()
+        .map(Some)
+}
+
+/// Whether the file at `path` starts with the ELF magic number.
+pub fn is_elf(path: &Path) -> bool {
+    let mut magic = [0u8; 4];
+    File::open(path)
+        .and_then(|mut file| file.read_exact(&mut magic))
+        .is_ok_and(|_| &magic == ELF_MAGIC)
+}
+
+/// The shared libraries the ELF file at `path` needs (`DT_NEEDED`), e.g.
+/// `libfmt.so.10`. `None` for files without a dynamic section, such as
+/// statically linked executables and object files.
+pub fn needed_libraries(path: &Path) -> Result<Option<Vec<String>>> {
+    let data = fs::read(path).with_context(|| format!("Failed to read {:?}", path))?;
+    parse_needed(&data).with_context(|| format!("Failed to read the ELF file {:?}", path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE_ADDRESS: u64 = 0x40_0000;
+
+    // A minimal ELF image: header, a PT_LOAD segment mapping the whole file,
+    // an optional PT_DYNAMIC segment and the dynamic string table.
+    struct Fixture {
+        is_64: bool,
+        big_endian: bool,
+        data: Vec<u8>,
+    }
+
+    impl Fixture {
+        fn put(&mut self, offset: usize, bytes: &[u8]) {
+            if self.data.len() < offset + bytes.len() {
+                self.data.resize(offset + bytes.len(), 0);
+            }
+            self.data[offset..offset + bytes.len()].copy_from_slice(bytes);
+        }
+
+        fn put_int(&mut self, offset: usize, value: u64, size: usize) {
+            let bytes = if self.big_endian {
+                value.to_be_bytes()[8 - size..].to_vec()
+            } else {
+                value.to_le_bytes()[..size].to_vec()
+            };
+            self.put(offset, &bytes);
+        }
+
+        fn put_word(&mut self, offset: usize, value: u64) {
+            self.put_int(offset, value, if self.is_64 { 8 } else { 4 });
+        }
+
+        fn build(is_64: bool, big_endian: bool, needed: Option<&[&str]>) -> Vec<u8> {
+            let mut elf = Fixture { is_64, big_endian, data: Vec::new() };
+            let (header_size, phentsize) = if is_64 { (64, 56) } else { (52, 32) };
+            let word = if is_64 { 8 } else { 4 };
+            let phnum = if needed.is_some() { 2 } else { 1 };
+            let dynamic = header_size + phnum * phentsize;
+
+            let mut strtab = vec![0u8];
+            let mut entries = Vec::new();
+            for name in needed.unwrap_or_default() {
+                entries.push((DT_NEEDED, strtab.len() as u64));
+                strtab.extend_from_slice(name.as_bytes());
+                strtab.push(0);
+            }
+            let strtab_offset = dynamic + (entries.len() + 2) * 2 * word;
+            entries.push((DT_STRTAB, BASE_ADDRESS + strtab_offset as u64));
+            entries.push((DT_NULL, 0));
+            let dynamic_size = entries.len() * 2 * word;
+
+            elf.put(0, ELF_MAGIC);
+            elf.put(4, &[if is_64 { 2 } else { 1 }, if big_endian { 2 } else { 1 }, 1]);
+            if is_64 {
+                elf.put_int(0x20, header_size as u64, 8);
+                elf.put_int(0x36, phentsize as u64, 2);
+                elf.put_int(0x38, phnum as u64, 2);
+            } else {
+                elf.put_int(0x1c, header_size as u64, 4);
+                elf.put_int(0x2a, phentsize as u64, 2);
+                elf.put_int(0x2c, phnum as u64, 2);
+            }
+            if needed.is_some() {
+                for (index, (tag, value)) in entries.iter().enumerate() {
+                    elf.put_word(dynamic + index * 2 * word, *tag);
+                    elf.put_word(dynamic + index * 2 * word + word, *value);
+                }
+                elf.put(strtab_offset, &strtab);
+            } else {
+                elf.data.resize(dynamic, 0);
+            }
+            let file_size = elf.data.len() as u64;
+
+            let segments = [
+                (PT_LOAD, 0, BASE_ADDRESS, file_size),
+                (PT_DYNAMIC, dynamic as u64, BASE_ADDRESS + dynamic as u64, dynamic_size as u64),
+            ];
+            for (index, (kind, offset, vaddr, filesz)) in segments.into_iter().take(phnum).enumerate() {
+                let base = header_size + index * phentsize;
+                elf.put_int(base, u64::from(kind), 4);
+                if is_64 {
+                    elf.put_int(base + 8, offset, 8);
+                    elf.put_int(base + 16, vaddr, 8);
+                    elf.put_int(base + 32, filesz, 8);
+                } else {
+                    elf.put_int(base + 4, offset, 4);
+                    elf.put_int(base + 8, vaddr, 4);
+                    elf.put_int(base + 16, filesz, 4);
+                }
+            }
+            elf.data
+        }
+    }
+
+    const LAYOUTS: [(bool, bool); 4] = [(false, false), (false, true), (true, false), (true, true)];
+
+    #[test]
+    fn reads_needed_libraries_of_every_layout() {
+        for (is_64, big_endian) in LAYOUTS {
+            let data = Fixture::build(is_64, big_endian, Some(&["libfmt.so.10", "libc.so.6"]));
+            assert_eq!(
+                parse_needed(&data).unwrap(),
+                Some(vec!["libfmt.so.10".to_string(), "libc.so.6".to_string()]),
+                "64-bit: {}, big-endian: {}",
+                is_64,
+                big_endian
+            );
+        }
+    }
+
+    #[test]
+    fn static_files_have_no_dynamic_section() {
+        for (is_64, big_endian) in LAYOUTS {
+            assert_eq!(parse_needed(&Fixture::build(is_64, big_endian, None)).unwrap(), None);
+            assert_eq!(
+                parse_needed(&Fixture::build(is_64, big_endian, Some(&[]))).unwrap(),
+                Some(Vec::new())
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_truncated_files() {
+        for (is_64, big_endian) in LAYOUTS {
+            let data = Fixture::build(is_64, big_endian, Some(&["libfmt.so.10"]));
+            // Inside the string table, the dynamic section, the program headers and the header.
+            for length in [data.len() - 3, data.len() - 20, 70, 40, 6] {
+                assert!(
+                    parse_needed(&data[..length]).is_err(),
+                    "64-bit: {}, big-endian: {}, length {}",
+                    is_64,
+                    big_endian,
+                    length
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        assert!(parse_needed(b"#!/bin/sh\necho hi\n").is_err());
+        assert!(parse_needed(b"\x7fELF\x03\x01\x01").is_err());
+        assert!(parse_needed(b"\x7fELF\x02\x03\x01").is_err());
+    }
+}
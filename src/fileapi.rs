@@ -0,0 +1,165 @@
+// src/fileapi.rs
+//! Reads the build tree through the [CMake File API](https://cmake.org/cmake/help/latest/manual/cmake-file-api.7.html).
+//!
+//! A `codemodel-v2` query is written into the build directory before CMake
+//! configures it; CMake then answers with a set of JSON reply files under
+//! `.cmake/api/v1/reply/` describing every target and the artifacts it produces.
+use crate::utils;
+use anyhow::{bail, Context, Result};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const API_DIRNAME: &str = ".cmake/api/v1";
+const CODEMODEL_QUERY: &str = "codemodel-v2";
+
+/// An executable target found in the File API reply.
+#[derive(Debug, Clone)]
+pub struct ExecutableTarget {
+    pub name: String,
+    /// Absolute path of the built executable.
+    pub artifact: PathBuf,
+}
+
+#[derive(Deserialize)]
+struct ReplyIndex {
+    reply: HashMap<String, ReplyObject>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ReplyObject {
+    json_file: String,
+}
+
+#[derive(Deserialize)]
+struct Codemodel {
+    configurations: Vec<CodemodelConfiguration>,
+}
+
+#[derive(Deserialize)]
+struct CodemodelConfiguration {
+    name: String,
+    targets: Vec<CodemodelTarget>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CodemodelTarget {
+    json_file: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TargetReply {
+    name: String,
+    #[serde(rename = "type")]
+    kind: String,
+    name_on_disk: Option<String>,
+    #[serde(default)]
+    artifacts: Vec<Artifact>,
+}
+
+#[derive(Deserialize)]
+struct Artifact {
+    path: String,
+}
+
+fn query_path(build_dir: &Path) -> PathBuf {
+    build_dir.join(API_DIRNAME).join("query").join(CODEMODEL_QUERY)
+}
+
+fn reply_dir(build_dir: &Path) -> PathBuf {
+    build_dir.join(API_DIRNAME).join("reply")
+}
+
+/// Whether `build_dir` already asks CMake for a codemodel reply.
+pub fn has_codemodel_query(build_dir: &Path) -> bool {
+    query_path(build_dir).exists()
+}
+
+/// Asks CMake for a `codemodel-v2` reply on the next configure of `build_dir`.
+pub fn write_codemodel_query(build_dir: &Path) -> Result<()> {
+    let path = query_path(build_dir);
+    let parent = path.parent().expect("query path always has a parent");
+    utils::create_dir_all(parent)
+        .with_context(|| format!("Failed to create File API query directory {:?}", parent))?;
+    // The query is an empty file; its name is what selects the reply.
+    utils::write(&path, "").with_context(|| format!("Failed to write File API query {:?}", path))
+}
+
+fn read_reply<T: DeserializeOwned>(path: &Path) -> Result<T> {
+    let content =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {:?}", path))?;
+    serde_json::from_str(&content).with_context(|| format!("Failed to parse {:?}", path))
+}
+
+// The newest `index-*.json`; the names sort by the time CMake wrote them.
+fn latest_index(reply_dir: &Path) -> Result<PathBuf> {
+    let entries = fs::read_dir(reply_dir).with_context(|| {
+        format!(
+            "No CMake File API reply in {:?}. Reconfigure the project with `rig build --clean`.",
+            reply_dir
+        )
+    })?;
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with("index-") && n.ends_with(".json"))
+        })
+        .max()
+        .with_context(|| format!("No File API index file in {:?}", reply_dir))
+}
+
+/// Lists the executable targets of the configured `build_dir`.
+///
+/// For multi-config generators only the first configuration is used, which is
+/// the one `cmake --build` builds when no `--config` is given.
+pub fn executable_targets(build_dir: &Path) -> Result<Vec<ExecutableTarget>> {
+    let reply_dir = reply_dir(build_dir);
+    let index: ReplyIndex = read_reply(&latest_index(&reply_dir)?)?;
+    let Some(codemodel_ref) = index.reply.get(CODEMODEL_QUERY) else {
+        bail!(
+            "The CMake File API reply in {:?} has no {}. Reconfigure the project with `rig build --clean`.",
+            reply_dir,
+            CODEMODEL_QUERY
+        );
+    };
+    let codemodel: Codemodel = read_reply(&reply_dir.join(&codemodel_ref.json_file))?;
+    let Some(configuration) = codemodel.configurations.first() else {
+        return Ok(Vec::new());
+    };
+
+    let mut executables = Vec::new();
+    for target_ref in &configuration.targets {
+        let target: TargetReply = read_reply(&reply_dir.join(&target_ref.json_file))
+            .with_context(|| format!("Failed to read targets of configuration '{}'", configuration.name))?;
+        if target.kind != "EXECUTABLE" {
+            continue;
+        }
+        // MSVC builds list the .pdb as well; prefer the artifact named like the executable.
+        let artifact = target
+            .artifacts
+            .iter()
+            .find(|a| {
+                target.name_on_disk.as_deref().is_some_and(|name| {
+                    Path::new(&a.path).file_name().and_then(|n| n.to_str()) == Some(name)
+                })
+            })
+            .or_else(|| target.artifacts.first());
+        if let Some(artifact) = artifact {
+            executables.push(ExecutableTarget {
+                name: target.name,
+                // Relative artifact paths are relative to the top of the build tree.
+                artifact: build_dir.join(&artifact.path),
+            });
+        }
+    }
+    executables.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(executables)
+}
@@ -0,0 +1,149 @@
+// src/fingerprint.rs
+//! Fingerprints of the inputs of a CMake configure step.
+//!
+//! CMake re-runs itself when CMakeLists.txt changes, but not when the presets,
+//! the vcpkg manifest or the vcpkg checkout change. After each successful
+//! configure, rig stores a SHA-256 of each of those inputs in the build
+//! directory; a build reconfigures when any of them differs.
+use crate::config::{ProjectConfig, RIG_TOML_FILENAME};
+use crate::presets::{EffectiveConfigurePreset, Presets};
+use crate::utils;
+use crate::vcpkg::{self, VCPKG_CONFIGURATION_FILENAME, VCPKG_JSON_FILENAME};
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+const FINGERPRINT_FILENAME: &str = "rig-configure.sha256";
+
+/// One SHA-256 per configure input, keyed by a description of the input.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Fingerprint {
+    entries: Vec<(String, String)>,
+}
+
+/// The SHA-256 of `data` as lowercase hex.
+pub fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .fold(String::new(), |mut hex, byte| {
+            let _ = write!(hex, "{:02x}", byte);
+            hex
+        })
+}
+
+// Missing files hash differently from empty ones.
+fn file_input(path: &Path) -> Vec<u8> {
+    fs::read(path).unwrap_or_else(|_| b"<missing>".to_vec())
+}
+
+// The resolved preset as text, so changes anywhere in an `inherits` chain or
+// in an `$env{}` value show up.
+fn preset_input(preset: &EffectiveConfigurePreset) -> Vec<u8> {
+    let mut text = format!("binaryDir={}\n", preset.binary_dir.display());
+    if let Some(install_dir) = &preset.install_dir {
+        let _ = writeln!(text, "installDir={}", install_dir.display());
+    }
+    if let Some(generator) = &preset.generator {
+        let _ = writeln!(text, "generator={}", generator);
+    }
+    for (name, value) in &preset.cache_variables {
+        let _ = writeln!(text, "-D{}={}", name, value);
+    }
+    for (name, value) in &preset.environment {
+        let _ = writeln!(text, "env {}={}", name, value);
+    }
+    text.into_bytes()
+}
+
+impl Fingerprint {
+    /// Fingerprints the configure inputs of `preset` in `config`'s project.
+    pub fn compute(
+        config: &ProjectConfig,
+        presets: &Presets,
+        preset: &EffectiveConfigurePreset,
+    ) -> Fingerprint {
+        let project_root = &config.project_path;
+        let mut entries = Vec::new();
+        let mut add = |label: String, data: &[u8]| entries.push((label, sha256_hex(data)));
+
+        for file in presets.files() {
+            let label = file.strip_prefix(project_root).unwrap_or(file);
+            add(label.display().to_string(), &file_input(file));
+        }
+        add(format!("preset {}", config.preset), &preset_input(preset));
+        for filename in [VCPKG_JSON_FILENAME, VCPKG_CONFIGURATION_FILENAME] {
+            add(filename.to_string(), &file_input(&project_root.join(filename)));
+        }
+        // Only the rig.toml settings that change what gets configured; editing
+        // `run.args` or comments must not trigger a reconfigure.
+        let settings = format!(
+            "preset {}\ncompiler {}\n",
+            config.preset,
+            config.compiler.as_deref().unwrap_or("<none>")
+        );
+        add(format!("{} settings", RIG_TOML_FILENAME), settings.as_bytes());
+        // The vcpkg root may come from --vcpkg-root or VCPKG_ROOT rather than a file.
+        let (vcpkg_root, vcpkg_commit) = match config.vcpkg() {
+            Ok(paths) => (
+                paths.root.display().to_string(),
+                vcpkg::checkout_head(paths).unwrap_or_else(|_| "<unknown>".to_string()),
+            ),
+            Err(_) => ("<none>".to_string(), "<none>".to_string()),
+        };
+        add("vcpkg root".to_string(), vcpkg_root.as_bytes());
+        add("vcpkg commit".to_string(), vcpkg_commit.as_bytes());
+        Fingerprint { entries }
+    }
+
+    /// The fingerprint stored by the last successful configure of `build_dir`.
+    pub fn read(build_dir: &Path) -> Option<Fingerprint> {
+        let content = fs::read_to_string(build_dir.join(FINGERPRINT_FILENAME)).ok()?;
+        let entries = content
+            .lines()
+            .filter_map(|line| line.split_once("  "))
+            .map(|(hash, label)| (label.to_string(), hash.to_string()))
+            .collect();
+        Some(Fingerprint { entries })
+    }
+
+    /// Stores the fingerprint in `build_dir`, in `sha256sum` format.
+    pub fn write(&self, build_dir: &Path) -> Result<()> {
+        let content: String = self
+            .entries
+            .iter()
+            .map(|(label, hash)| format!("{}  {}\n", hash, label))
+            .collect();
+        let path = build_dir.join(FINGERPRINT_FILENAME);
+        utils::write(&path, content).with_context(|| format!("Failed to write {:?}", path))
+    }
+
+    /// Removes the stored fingerprint, so the next build reconfigures.
+    pub fn remove(build_dir: &Path) -> Result<()> {
+        let path = build_dir.join(FINGERPRINT_FILENAME);
+        if path.exists() {
+            utils::remove_file(&path).with_context(|| format!("Failed to remove {:?}", path))?;
+        }
+        Ok(())
+    }
+
+    /// The inputs that are new or different in `self` compared to `previous`,
+    /// plus the ones that no longer exist.
+    pub fn changed_inputs<'a>(&'a self, previous: &'a Fingerprint) -> Vec<&'a str> {
+        let mut changed: Vec<&str> = self
+            .entries
+            .iter()
+            .filter(|entry| !previous.entries.contains(entry))
+            .map(|(label, _)| label.as_str())
+            .collect();
+        changed.extend(
+            previous
+                .entries
+                .iter()
+                .filter(|(label, _)| !self.entries.iter().any(|(l, _)| l == label))
+                .map(|(label, _)| label.as_str()),
+        );
+        changed
+    }
+}
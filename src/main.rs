@@ -2,17 +2,30 @@
 mod cli;
 mod cmake;
 mod config;
+mod diagnostics;
+mod elf;
+mod fileapi;
+mod fingerprint;
+mod manifest;
+mod presets;
+mod sarif;
+mod sanitizers;
+mod toolchain;
+mod usage;
 mod utils;
 mod vcpkg;
 mod actions;
 
 use anyhow::Result;
 use clap::Parser;
+use std::time::Duration;
 
-use cli::{Args, CliCommand, CleanArgs}; // Added CleanArgs
+use cli::{Args, CliCommand, CleanArgs, PresetsCommand, ToolchainCommand}; // Added CleanArgs
+use config::ProjectConfig;
 
 fn main() -> Result<()> {
     let args = Args::parse();
+    utils::set_dry_run(args.dry_run);
 
     match args.command {
         CliCommand::New {
@@ -20,28 +33,210 @@ fn main() -> Result<()> {
             vcpkg_root,
             deps,
             std,
+            lib,
+            kind,
+            compiler,
+            sanitizers,
+            style,
+            clang_tidy,
         } => {
-            actions::new::new_project(name, vcpkg_root, deps, std)?;
+            let library_kind = lib.then_some(kind);
+            let template = actions::new::TemplateOptions {
+                sanitizers: sanitizers.unwrap_or_default(),
+                format_style: style,
+                clang_tidy,
+            };
+            actions::new::new_project(
+                name,
+                vcpkg_root,
+                deps,
+                std,
+                library_kind,
+                compiler,
+                &template,
+            )?;
         }
-        CliCommand::Build { preset, clean } => {
-            actions::build::build_project(&preset, clean)?;
+        CliCommand::Init {
+            path,
+            vcpkg_root,
+            deps,
+            std,
+        } => {
+            actions::init::init_project(path, vcpkg_root, deps, std)?;
+        }
+        CliCommand::Build {
+            preset,
+            clean,
+            reconfigure,
+            fresh,
+            targets,
+            jobs,
+            keep_going,
+            verbose,
+            message_format,
+            sarif,
+            native_args,
+        } => {
+            let options = actions::build::BuildOptions {
+                targets,
+                jobs,
+                keep_going,
+                verbose,
+                native_args,
+                message_format,
+                sarif,
+            };
+            let reconfigure = if fresh {
+                actions::build::Reconfigure::Fresh
+            } else if reconfigure {
+                actions::build::Reconfigure::Always
+            } else {
+                actions::build::Reconfigure::IfChanged
+            };
+            let config = ProjectConfig::load(preset, None)?;
+            actions::build::build_project(&config, clean, reconfigure, &options)?;
         }
         CliCommand::Run {
             preset,
-            target,
+            bin,
             clean,
             executable_args,
         } => {
-            actions::run::run_project(&preset, target, clean, &executable_args)?;
+            let config = ProjectConfig::load(preset, None)?;
+            actions::run::run_project(&config, bin, clean, &executable_args)?;
+        }
+        CliCommand::Test {
+            preset,
+            tests_regex,
+            exclude_regex,
+            label,
+            exclude_label,
+            no_build,
+            clean,
+            ctest_args,
+        } => {
+            let filters = actions::test::TestFilters {
+                include_regex: tests_regex,
+                exclude_regex,
+                label_regex: label,
+                exclude_label_regex: exclude_label,
+            };
+            let config = ProjectConfig::load(preset, None)?;
+            actions::test::test_project(&config, no_build, clean, &filters, &ctest_args)?;
+        }
+        CliCommand::Watch {
+            action,
+            preset,
+            bin,
+            debounce,
+            args,
+        } => {
+            let options = actions::watch::WatchOptions {
+                action,
+                preset,
+                bin,
+                debounce: Duration::from_millis(debounce),
+                args,
+            };
+            actions::watch::watch_project(&options)?;
+        }
+        CliCommand::Install {
+            preset,
+            prefix,
+            component,
+        } => {
+            let options = actions::install::InstallOptions { prefix, component };
+            let config = ProjectConfig::load(preset, None)?;
+            actions::install::install_project(&config, &options)?;
+        }
+        CliCommand::Dist { preset, output } => {
+            let options = actions::dist::DistOptions { output_dir: output };
+            let config = ProjectConfig::load(Some(preset), None)?;
+            actions::dist::dist_project(&config, &options)?;
         }
         CliCommand::Add {
             dependencies,
             vcpkg_root,
+            no_default_features,
+            platform,
+            force,
         } => {
-            actions::add::add_dependencies(&dependencies, vcpkg_root)?;
+            let config = ProjectConfig::load(None, vcpkg_root)?;
+            actions::add::add_dependencies(
+                &config,
+                &dependencies,
+                no_default_features,
+                platform,
+                force,
+            )?;
+        }
+        CliCommand::Remove { dependencies } => {
+            let config = ProjectConfig::load(None, None)?;
+            actions::remove::remove_dependencies(&config, &dependencies)?;
         }
-        CliCommand::Clean(CleanArgs { preset, all }) => { // Added handler for Clean
-            actions::clean::clean_project(preset, all)?;
+        CliCommand::Update { vcpkg_root } => {
+            let config = ProjectConfig::load(None, vcpkg_root)?;
+            actions::update::update_baseline(&config)?;
+        }
+        CliCommand::Clean(CleanArgs { preset, all, install }) => { // Added handler for Clean
+            let config = ProjectConfig::load(preset, None)?;
+            actions::clean::clean_project(&config, all, install)?;
+        }
+        CliCommand::Presets { command } => {
+            let config = ProjectConfig::load(None, None)?;
+            match command {
+                PresetsCommand::List => actions::presets::list_presets(&config)?,
+                PresetsCommand::Show { name } => actions::presets::show_preset(&config, &name)?,
+                PresetsCommand::Add {
+                    name,
+                    inherits,
+                    cache,
+                    env,
+                    display_name,
+                    shared,
+                } => {
+                    let preset = actions::presets::NewPreset {
+                        name,
+                        display_name,
+                        inherits,
+                        cache,
+                        env,
+                        shared,
+                    };
+                    actions::presets::add_preset(&config, &preset)?;
+                }
+            }
+        }
+        CliCommand::Toolchain { command } => match command {
+            ToolchainCommand::List => {
+                // Listing works outside of a project too.
+                let config = ProjectConfig::load(None, None).ok();
+                actions::toolchain::list_toolchains(config.as_ref());
+            }
+            ToolchainCommand::Use { name } => {
+                let config = ProjectConfig::load(None, None)?;
+                actions::toolchain::use_toolchain(&config, &name)?;
+            }
+        },
+        CliCommand::Fmt { check, diff } => {
+            let config = ProjectConfig::load(None, None)?;
+            actions::fmt::format_project(&config, check, diff)?;
+        }
+        CliCommand::Lint {
+            preset,
+            fix,
+            checks,
+            fail_on,
+            sarif,
+        } => {
+            let options = actions::lint::LintOptions {
+                fix,
+                checks,
+                fail_on,
+                sarif,
+            };
+            let config = ProjectConfig::load(preset, None)?;
+            actions::lint::lint_project(&config, &options)?;
         }
     }
     Ok(())
@@ -0,0 +1,401 @@
+// src/manifest.rs
+//! Typed model of a vcpkg manifest (`vcpkg.json`).
+//!
+//! Unknown fields are kept in `extra` maps and the key order of the original
+//! file is restored on save, at every nesting level, so loading and saving a
+//! manifest does not lose or reshuffle anything rig does not understand.
+use crate::utils;
+use anyhow::{Context, Result};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::Path;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "kebab-case")]
+pub struct VcpkgManifest {
+    #[serde(rename = "$schema", skip_serializing_if = "Option::is_none")]
+    pub schema: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version_semver: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version_date: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version_string: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub port_version: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub builtin_baseline: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dependencies: Option<Vec<Dependency>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_features: Option<Vec<FeatureRef>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub features: Option<IndexMap<String, ManifestFeature>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub overrides: Option<Vec<Override>>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+
+    /// The document this manifest was read from, whose key order is restored on save.
+    #[serde(skip)]
+    original: Value,
+}
+
+/// An entry of `dependencies`: either a bare port name or an object.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum Dependency {
+    Name(String),
+    Detailed(DependencyDetails),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "kebab-case")]
+pub struct DependencyDetails {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub features: Option<Vec<FeatureRef>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_features: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub host: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub platform: Option<String>,
+    #[serde(rename = "version>=", skip_serializing_if = "Option::is_none")]
+    pub version_ge: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// A feature reference: `"ssl"` or `{ "name": "ssl", "platform": "windows" }`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum FeatureRef {
+    Name(String),
+    Detailed {
+        name: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        platform: Option<String>,
+        #[serde(flatten)]
+        extra: Map<String, Value>,
+    },
+}
+
+/// A feature declared by the manifest itself, under `features`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "kebab-case")]
+pub struct ManifestFeature {
+    /// A string or an array of strings.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dependencies: Option<Vec<Dependency>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub supports: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "kebab-case")]
+pub struct Override {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version_semver: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version_date: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version_string: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub port_version: Option<u32>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Dependency {
+    pub fn name(&self) -> &str {
+        match self {
+            Dependency::Name(name) => name,
+            Dependency::Detailed(details) => &details.name,
+        }
+    }
+
+    fn into_details(self) -> DependencyDetails {
+        match self {
+            Dependency::Name(name) => DependencyDetails {
+                name,
+                ..Default::default()
+            },
+            Dependency::Detailed(details) => details,
+        }
+    }
+}
+
+impl DependencyDetails {
+    /// Adds the features of `other` that are not listed yet and takes every
+    /// qualifier `other` sets; qualifiers it leaves unset are kept.
+    pub fn merge(&mut self, other: DependencyDetails) {
+        for feature in other.features.into_iter().flatten() {
+            let features = self.features.get_or_insert_with(Vec::new);
+            if !features.iter().any(|f| f.name() == feature.name()) {
+                features.push(feature);
+            }
+        }
+        if other.default_features.is_some() {
+            self.default_features = other.default_features;
+        }
+        if other.host.is_some() {
+            self.host = other.host;
+        }
+        if other.platform.is_some() {
+            self.platform = other.platform;
+        }
+        if other.version_ge.is_some() {
+            self.version_ge = other.version_ge;
+        }
+        self.extra.extend(other.extra);
+    }
+}
+
+impl FeatureRef {
+    pub fn name(&self) -> &str {
+        match self {
+            FeatureRef::Name(name) => name,
+            FeatureRef::Detailed { name, .. } => name,
+        }
+    }
+}
+
+// Reorders the keys of every object in `value` to follow `original`; keys the
+// original does not have go last. Array elements are paired by their `name`
+// field (dependencies, overrides, ...) when they have one, else by position.
+fn order_like(value: Value, original: &Value) -> Value {
+    match (value, original) {
+        (Value::Object(mut fields), Value::Object(original_fields)) => {
+            let mut ordered = Map::new();
+            for (key, original_value) in original_fields {
+                if let Some(value) = fields.shift_remove(key) {
+                    ordered.insert(key.clone(), order_like(value, original_value));
+                }
+            }
+            ordered.extend(fields);
+            Value::Object(ordered)
+        }
+        (Value::Array(items), Value::Array(original_items)) => Value::Array(
+            items
+                .into_iter()
+                .enumerate()
+                .map(|(index, item)| {
+                    let counterpart = match item.get("name").and_then(Value::as_str) {
+                        Some(name) => original_items
+                            .iter()
+                            .find(|o| o.get("name").and_then(Value::as_str) == Some(name)),
+                        None => original_items.get(index),
+                    };
+                    match counterpart {
+                        Some(counterpart) => order_like(item, counterpart),
+                        None => item,
+                    }
+                })
+                .collect(),
+        ),
+        (value, _) => value,
+    }
+}
+
+impl VcpkgManifest {
+    pub fn parse(content: &str) -> Result<Self> {
+        let value: Value = serde_json::from_str(content).context("Invalid JSON")?;
+        let mut manifest: Self =
+            serde_json::from_value(value.clone()).context("Invalid vcpkg manifest")?;
+        manifest.original = value;
+        Ok(manifest)
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content =
+            fs::read_to_string(path).with_context(|| format!("Failed to read {:?}", path))?;
+        Self::parse(&content).with_context(|| format!("Failed to parse {:?}", path))
+    }
+
+    /// Serializes the manifest, keeping the key order of the original file.
+    pub fn to_json_string(&self) -> Result<String> {
+        let value = serde_json::to_value(self).context("Failed to serialize vcpkg manifest")?;
+        let mut out = serde_json::to_string_pretty(&order_like(value, &self.original))
+            .context("Failed to serialize vcpkg manifest")?;
+        out.push('\n');
+        Ok(out)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        utils::write(path, self.to_json_string()?)
+            .with_context(|| format!("Failed to write {:?}", path))
+    }
+
+    /// The project's version, from whichever `version*` field is set.
+    pub fn version_text(&self) -> Option<&str> {
+        self.version
+            .as_deref()
+            .or(self.version_semver.as_deref())
+            .or(self.version_date.as_deref())
+            .or(self.version_string.as_deref())
+    }
+
+    pub fn dependencies(&self) -> &[Dependency] {
+        self.dependencies.as_deref().unwrap_or_default()
+    }
+
+    pub fn find_dependency(&self, name: &str) -> Option<&Dependency> {
+        self.dependencies().iter().find(|d| d.name() == name)
+    }
+
+    pub fn has_dependency(&self, name: &str) -> bool {
+        self.find_dependency(name).is_some()
+    }
+
+    /// Merges `dependency` into the entry with the same name in `dependencies`
+    /// (see [`DependencyDetails::merge`]), or appends it. Returns whether an
+    /// existing entry was updated.
+    pub fn merge_dependency(&mut self, dependency: Dependency) -> bool {
+        let dependencies = self.dependencies.get_or_insert_with(Vec::new);
+        match dependencies.iter_mut().find(|d| d.name() == dependency.name()) {
+            Some(existing) => {
+                if let Dependency::Name(name) = existing {
+                    *existing = Dependency::Detailed(DependencyDetails {
+                        name: name.clone(),
+                        ..Default::default()
+                    });
+                }
+                if let Dependency::Detailed(details) = existing {
+                    details.merge(dependency.into_details());
+                }
+                true
+            }
+            None => {
+                dependencies.push(dependency);
+                false
+            }
+        }
+    }
+
+    /// Removes every `dependencies` entry for `name`, returning whether any was present.
+    pub fn remove_dependency(&mut self, name: &str) -> bool {
+        let Some(dependencies) = self.dependencies.as_mut() else {
+            return false;
+        };
+        let before = dependencies.len();
+        dependencies.retain(|d| d.name() != name);
+        dependencies.len() != before
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Every object's keys, depth first, so nested order differences show up.
+    fn key_paths(value: &Value, prefix: &str, out: &mut Vec<String>) {
+        match value {
+            Value::Object(fields) => {
+                for (key, value) in fields {
+                    let path = format!("{}/{}", prefix, key);
+                    out.push(path.clone());
+                    key_paths(value, &path, out);
+                }
+            }
+            Value::Array(items) => {
+                for (index, item) in items.iter().enumerate() {
+                    key_paths(item, &format!("{}/{}", prefix, index), out);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn assert_round_trip(content: &str) {
+        let original: Value = serde_json::from_str(content).unwrap();
+        let saved = VcpkgManifest::parse(content).unwrap().to_json_string().unwrap();
+        let reparsed: Value = serde_json::from_str(&saved).unwrap();
+        assert_eq!(reparsed, original);
+
+        let (mut expected, mut actual) = (Vec::new(), Vec::new());
+        key_paths(&original, "", &mut expected);
+        key_paths(&reparsed, "", &mut actual);
+        assert_eq!(actual, expected);
+        assert_eq!(saved, serde_json::to_string_pretty(&original).unwrap() + "\n");
+    }
+
+    #[test]
+    fn round_trips_minified_manifest() {
+        assert_round_trip(
+            r#"{"name":"app","version-string":"1.0","dependencies":["fmt",{"version>=":"8.1.0","name":"curl","features":["ssl"]}],"builtin-baseline":"0cf34c184ce990471a2d5a7a7b5a5b3cac4cd0a7"}"#,
+        );
+    }
+
+    #[test]
+    fn round_trips_object_dependencies_with_unknown_keys() {
+        assert_round_trip(
+            r#"{
+  "dependencies": [
+    {
+      "platform": "linux",
+      "$comment": "needed for TLS",
+      "name": "openssl",
+      "x-custom": { "z": 1, "a": 2 },
+      "features": [{ "platform": "windows", "name": "tools" }, "ssl"]
+    }
+  ],
+  "overrides": [{ "version": "3.0.0", "name": "openssl", "x-reason": "CVE" }],
+  "name": "app"
+}"#,
+        );
+    }
+
+    #[test]
+    fn round_trips_embedded_configuration() {
+        assert_round_trip(
+            r#"{
+  "vcpkg-configuration": {
+    "registries": [
+      { "name": "microsoft", "location": "https://example.com/catalog.zip", "kind": "artifact" }
+    ],
+    "default-registry": {
+      "repository": "https://github.com/microsoft/vcpkg",
+      "kind": "git",
+      "baseline": "0cf34c184ce990471a2d5a7a7b5a5b3cac4cd0a7"
+    }
+  },
+  "dependencies": ["fmt"]
+}"#,
+        );
+    }
+
+    #[test]
+    fn merged_entries_keep_their_key_order() {
+        let mut manifest = VcpkgManifest::parse(
+            r#"{"dependencies":[{"platform":"linux","name":"curl","x-note":"keep"}]}"#,
+        )
+        .unwrap();
+        manifest.merge_dependency(Dependency::Detailed(DependencyDetails {
+            name: "curl".to_string(),
+            features: Some(vec![FeatureRef::Name("ssl".to_string())]),
+            ..Default::default()
+        }));
+        manifest.merge_dependency(Dependency::Name("fmt".to_string()));
+        let saved: Value = serde_json::from_str(&manifest.to_json_string().unwrap()).unwrap();
+        let curl = saved["dependencies"][0].as_object().unwrap();
+        assert_eq!(
+            curl.keys().collect::<Vec<_>>(),
+            ["platform", "name", "x-note", "features"]
+        );
+        assert_eq!(saved["dependencies"][1], "fmt");
+    }
+}
@@ -0,0 +1,922 @@
+// src/presets.rs
+//! Typed model of `CMakePresets.json` and `CMakeUserPresets.json`.
+//!
+//! Presets are read from both files (the user file implicitly includes the
+//! project file) and from any `include`d files. Looking up a configure preset
+//! resolves its `inherits` chain and expands the preset macros (`${sourceDir}`,
+//! `${presetName}`, `$env{...}`, `$penv{...}`, ...), giving the effective
+//! binary directory, generator, cache variables and environment.
+use crate::cmake::{CMAKE_PRESETS_FILENAME, CMAKE_USER_PRESETS_FILENAME};
+use anyhow::{bail, Context, Result};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+struct PresetsFile {
+    #[serde(default)]
+    include: Vec<String>,
+    #[serde(default)]
+    configure_presets: Vec<ConfigurePreset>,
+    #[serde(default)]
+    build_presets: Vec<NamedPreset>,
+    #[serde(default)]
+    test_presets: Vec<NamedPreset>,
+}
+
+// Build and test presets are only tracked by name.
+#[derive(Deserialize, Debug)]
+struct NamedPreset {
+    name: String,
+}
+
+/// `inherits` is either a single preset name or a list of them.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(untagged)]
+enum Inherits {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl ConfigurePreset {
+    /// The presets named in `inherits`, in order.
+    pub fn inherits(&self) -> Vec<&str> {
+        self.inherits.as_ref().map(Inherits::names).unwrap_or_default()
+    }
+
+    // `${fileDir}` is the directory of the file declaring a field, which for
+    // inherited fields differs from the file of the preset being resolved, so
+    // it is expanded before the fields are merged.
+    fn with_file_dir(&self, file_dir: &Path) -> ConfigurePreset {
+        let file_dir = file_dir.to_string_lossy();
+        let expand = |value: &str| value.replace("${fileDir}", &file_dir);
+        let expand_value = |value: &Value| match value {
+            Value::String(s) => Value::String(expand(s)),
+            Value::Object(map) => Value::Object(
+                map.iter()
+                    .map(|(k, v)| match v {
+                        Value::String(s) if k == "value" => (k.clone(), Value::String(expand(s))),
+                        other => (k.clone(), other.clone()),
+                    })
+                    .collect(),
+            ),
+            other => other.clone(),
+        };
+        ConfigurePreset {
+            binary_dir: self.binary_dir.as_deref().map(expand),
+            install_dir: self.install_dir.as_deref().map(expand),
+            cache_variables: self
+                .cache_variables
+                .iter()
+                .map(|(key, value)| (key.clone(), value.as_ref().map(expand_value)))
+                .collect(),
+            environment: self
+                .environment
+                .iter()
+                .map(|(key, value)| (key.clone(), value.as_deref().map(expand)))
+                .collect(),
+            ..self.clone()
+        }
+    }
+}
+
+impl Inherits {
+    fn names(&self) -> Vec<&str> {
+        match self {
+            Inherits::One(name) => vec![name.as_str()],
+            Inherits::Many(names) => names.iter().map(String::as_str).collect(),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigurePreset {
+    pub name: String,
+    #[serde(default)]
+    pub hidden: bool,
+    inherits: Option<Inherits>,
+    pub display_name: Option<String>,
+    generator: Option<String>,
+    binary_dir: Option<String>,
+    install_dir: Option<String>,
+    /// `null` values unset a variable set by an inherited preset.
+    #[serde(default)]
+    cache_variables: IndexMap<String, Option<Value>>,
+    #[serde(default)]
+    environment: IndexMap<String, Option<String>>,
+}
+
+// A configure preset together with the file that declared it (whose
+// directory is the value of `${fileDir}`).
+#[derive(Debug)]
+struct LoadedPreset {
+    preset: ConfigurePreset,
+    file: PathBuf,
+    file_dir: PathBuf,
+    /// Declared by CMakeUserPresets.json or one of its includes.
+    from_user_file: bool,
+}
+
+/// A configure preset after `inherits` and macro expansion.
+#[derive(Debug, Clone)]
+pub struct EffectiveConfigurePreset {
+    pub binary_dir: PathBuf,
+    pub install_dir: Option<PathBuf>,
+    pub generator: Option<String>,
+    pub cache_variables: IndexMap<String, String>,
+    pub environment: IndexMap<String, String>,
+}
+
+/// The effective settings of a resolved configure preset, one per line.
+pub fn effective_preset_lines(preset: &EffectiveConfigurePreset) -> Vec<String> {
+    let mut lines = vec![format!("  binaryDir: {}", preset.binary_dir.display())];
+    if let Some(install_dir) = &preset.install_dir {
+        lines.push(format!("  installDir: {}", install_dir.display()));
+    }
+    if let Some(generator) = &preset.generator {
+        lines.push(format!("  generator: {}", generator));
+    }
+    for (name, value) in &preset.cache_variables {
+        lines.push(format!("  -D {}={}", name, value));
+    }
+    for (name, value) in &preset.environment {
+        lines.push(format!("  env {}={}", name, value));
+    }
+    lines
+}
+
+/// Prints the effective settings of a resolved configure preset.
+pub fn print_effective_preset(preset: &EffectiveConfigurePreset) {
+    for line in effective_preset_lines(preset) {
+        println!("{}", line);
+    }
+}
+
+/// All presets visible from a project's source directory.
+#[derive(Debug)]
+pub struct Presets {
+    source_dir: PathBuf,
+    /// Every presets file read, includes first, in load order.
+    files: Vec<PathBuf>,
+    configure_presets: Vec<LoadedPreset>,
+    build_presets: Vec<String>,
+    test_presets: Vec<String>,
+}
+
+impl Presets {
+    /// Reads CMakePresets.json, CMakeUserPresets.json and their includes from `source_dir`.
+    pub fn load(source_dir: &Path) -> Result<Self> {
+        let mut presets = Presets {
+            source_dir: source_dir.to_path_buf(),
+            files: Vec::new(),
+            configure_presets: Vec::new(),
+            build_presets: Vec::new(),
+            test_presets: Vec::new(),
+        };
+        let mut visited = HashSet::new();
+        for (filename, from_user_file) in [
+            (CMAKE_PRESETS_FILENAME, false),
+            (CMAKE_USER_PRESETS_FILENAME, true),
+        ] {
+            let path = source_dir.join(filename);
+            if path.exists() {
+                presets.load_file(&path, from_user_file, &mut visited)?;
+            }
+        }
+        if visited.is_empty() {
+            bail!(
+                "No {} or {} found in {:?}.",
+                CMAKE_PRESETS_FILENAME,
+                CMAKE_USER_PRESETS_FILENAME,
+                source_dir
+            );
+        }
+        Ok(presets)
+    }
+
+    fn load_file(
+        &mut self,
+        path: &Path,
+        from_user_file: bool,
+        visited: &mut HashSet<PathBuf>,
+    ) -> Result<()> {
+        let canonical = path
+            .canonicalize()
+            .with_context(|| format!("Failed to resolve presets file {:?}", path))?;
+        // A file included from several places is only read once.
+        if !visited.insert(canonical.clone()) {
+            return Ok(());
+        }
+        let content =
+            fs::read_to_string(path).with_context(|| format!("Failed to read {:?}", path))?;
+        let file: PresetsFile =
+            serde_json::from_str(&content).with_context(|| format!("Failed to parse {:?}", path))?;
+        let file_dir = canonical
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| self.source_dir.clone());
+
+        for include in &file.include {
+            let include = self.expand_include(include, &file_dir);
+            self.load_file(&file_dir.join(include), from_user_file, visited)
+                .with_context(|| format!("Failed to load presets included from {:?}", path))?;
+        }
+        self.files.push(canonical.clone());
+        for preset in file.configure_presets {
+            if self.find(&preset.name).is_some() {
+                bail!("Duplicate configure preset '{}' in {:?}", preset.name, path);
+            }
+            self.configure_presets.push(LoadedPreset {
+                preset,
+                file: canonical.clone(),
+                file_dir: file_dir.clone(),
+                from_user_file,
+            });
+        }
+        self.build_presets
+            .extend(file.build_presets.into_iter().map(|p| p.name));
+        self.test_presets
+            .extend(file.test_presets.into_iter().map(|p| p.name));
+        Ok(())
+    }
+
+    // `include` entries may use the macros that do not depend on a preset.
+    fn expand_include(&self, include: &str, file_dir: &Path) -> String {
+        let context = MacroContext {
+            source_dir: &self.source_dir,
+            file_dir,
+            preset_name: "",
+            generator: "",
+            environment: &IndexMap::new(),
+        };
+        context.expand(include)
+    }
+
+    fn find(&self, name: &str) -> Option<&LoadedPreset> {
+        self.configure_presets.iter().find(|p| p.preset.name == name)
+    }
+
+    /// The presets files that were read, includes included.
+    pub fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    /// Every configure preset, hidden ones included, with the file declaring it.
+    pub fn configure_presets(&self) -> impl Iterator<Item = (&ConfigurePreset, &Path)> {
+        self.configure_presets
+            .iter()
+            .map(|p| (&p.preset, p.file.as_path()))
+    }
+
+    /// Whether a configure preset `name` exists, and if so whether it comes
+    /// from CMakeUserPresets.json (which CMakePresets.json presets cannot inherit from).
+    pub fn configure_preset_origin(&self, name: &str) -> Option<bool> {
+        self.find(name).map(|p| p.from_user_file)
+    }
+
+    pub fn has_build_preset(&self, name: &str) -> bool {
+        self.build_presets.iter().any(|n| n == name)
+    }
+
+    pub fn has_test_preset(&self, name: &str) -> bool {
+        self.test_presets.iter().any(|n| n == name)
+    }
+
+    /// The configure presets that can be selected with `--preset`, in file order.
+    pub fn visible_configure_presets(&self) -> impl Iterator<Item = &ConfigurePreset> {
+        self.configure_presets
+            .iter()
+            .map(|p| &p.preset)
+            .filter(|p| !p.hidden)
+    }
+
+    /// Resolves the configure preset `name` into its effective settings.
+    pub fn configure_preset(&self, name: &str) -> Result<EffectiveConfigurePreset> {
+        let Some(loaded) = self.find(name) else {
+            let available: Vec<&str> = self
+                .visible_configure_presets()
+                .map(|p| p.name.as_str())
+                .collect();
+            bail!(
+                "No configure preset named '{}'. Available presets: {}",
+                name,
+                available.join(", ")
+            );
+        };
+        if loaded.preset.hidden {
+            bail!("Configure preset '{}' is hidden and cannot be used directly.", name);
+        }
+        self.resolve(name)
+    }
+
+    /// Like `configure_preset`, but also resolves hidden presets.
+    pub fn resolve(&self, name: &str) -> Result<EffectiveConfigurePreset> {
+        let Some(loaded) = self.find(name) else {
+            bail!("No configure preset named '{}'.", name);
+        };
+        let merged = self.merge_inherited(name, &mut Vec::new())?;
+        let generator = merged.generator.clone();
+        let environment: IndexMap<String, String> = merged
+            .environment
+            .iter()
+            .filter_map(|(key, value)| Some((key.clone(), value.clone()?)))
+            .collect();
+
+        let context = MacroContext {
+            source_dir: &self.source_dir,
+            file_dir: &loaded.file_dir,
+            preset_name: name,
+            generator: generator.as_deref().unwrap_or(""),
+            environment: &environment,
+        };
+
+        let Some(binary_dir) = &merged.binary_dir else {
+            bail!(
+                "Configure preset '{}' does not set \"binaryDir\" (directly or through \"inherits\").",
+                name
+            );
+        };
+        Ok(EffectiveConfigurePreset {
+            binary_dir: context.expand_path(binary_dir),
+            install_dir: merged.install_dir.as_deref().map(|d| context.expand_path(d)),
+            cache_variables: merged
+                .cache_variables
+                .iter()
+                .filter_map(|(key, value)| {
+                    Some((key.clone(), context.expand(&cache_value_string(value.as_ref()?))))
+                })
+                .collect(),
+            environment: environment
+                .iter()
+                .map(|(key, value)| (key.clone(), context.expand(value)))
+                .collect(),
+            generator,
+        })
+    }
+
+    /// The effective binary directory of the configure preset `name`.
+    pub fn binary_dir(&self, name: &str) -> Result<PathBuf> {
+        Ok(self.configure_preset(name)?.binary_dir)
+    }
+
+    // Flattens the `inherits` chain of `name`. The preset's own values win; among
+    // parents, earlier entries of `inherits` win over later ones. `${fileDir}`
+    // is expanded per declaring file on the way.
+    fn merge_inherited(&self, name: &str, chain: &mut Vec<String>) -> Result<ConfigurePreset> {
+        if chain.iter().any(|n| n == name) {
+            chain.push(name.to_string());
+            bail!("Cyclic \"inherits\" between configure presets: {}", chain.join(" -> "));
+        }
+        let Some(loaded) = self.find(name) else {
+            bail!(
+                "Configure preset '{}' inherits from unknown preset '{}'",
+                chain.last().map(String::as_str).unwrap_or(name),
+                name
+            );
+        };
+        chain.push(name.to_string());
+
+        let own = &loaded.preset.with_file_dir(&loaded.file_dir);
+        let mut merged = ConfigurePreset {
+            name: own.name.clone(),
+            hidden: own.hidden,
+            inherits: None,
+            display_name: own.display_name.clone(),
+            generator: None,
+            binary_dir: None,
+            install_dir: None,
+            cache_variables: IndexMap::new(),
+            environment: IndexMap::new(),
+        };
+        let parents = own.inherits();
+        for parent in parents.iter().rev() {
+            let parent = self.merge_inherited(parent, chain)?;
+            merged.generator = parent.generator.or(merged.generator);
+            merged.binary_dir = parent.binary_dir.or(merged.binary_dir);
+            merged.install_dir = parent.install_dir.or(merged.install_dir);
+            merged.cache_variables.extend(parent.cache_variables);
+            merged.environment.extend(parent.environment);
+        }
+        chain.pop();
+
+        merged.generator = own.generator.clone().or(merged.generator);
+        merged.binary_dir = own.binary_dir.clone().or(merged.binary_dir);
+        merged.install_dir = own.install_dir.clone().or(merged.install_dir);
+        merged.cache_variables.extend(own.cache_variables.clone());
+        merged.environment.extend(own.environment.clone());
+        Ok(merged)
+    }
+}
+
+// `"ON"`, `true` and `{ "type": "BOOL", "value": "ON" }` are all valid cache values.
+fn cache_value_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Bool(true) => "TRUE".to_string(),
+        Value::Bool(false) => "FALSE".to_string(),
+        Value::Object(map) => map.get("value").map(cache_value_string).unwrap_or_default(),
+        other => other.to_string(),
+    }
+}
+
+// Environment values may refer to each other through `$env{}`; this bounds the nesting.
+const MAX_MACRO_DEPTH: usize = 16;
+
+struct MacroContext<'a> {
+    source_dir: &'a Path,
+    file_dir: &'a Path,
+    preset_name: &'a str,
+    generator: &'a str,
+    /// The preset's unexpanded environment, consulted by `$env{}` before the process environment.
+    environment: &'a IndexMap<String, String>,
+}
+
+impl MacroContext<'_> {
+    fn expand(&self, value: &str) -> String {
+        self.expand_with_depth(value, 0)
+    }
+
+    // Relative paths are relative to the source directory.
+    fn expand_path(&self, value: &str) -> PathBuf {
+        self.source_dir.join(self.expand(value))
+    }
+
+    fn expand_with_depth(&self, value: &str, depth: usize) -> String {
+        let mut out = String::with_capacity(value.len());
+        let mut rest = value;
+        while let Some(pos) = rest.find('$') {
+            out.push_str(&rest[..pos]);
+            let candidate = &rest[pos..];
+            match self.expand_macro(candidate, depth) {
+                Some((replacement, consumed)) => {
+                    out.push_str(&replacement);
+                    rest = &candidate[consumed..];
+                }
+                None => {
+                    out.push('$');
+                    rest = &candidate[1..];
+                }
+            }
+        }
+        out.push_str(rest);
+        out
+    }
+
+    // Expands the macro at the start of `text`, returning the replacement and
+    // the number of bytes it replaces. Unknown macros are left untouched.
+    fn expand_macro(&self, text: &str, depth: usize) -> Option<(String, usize)> {
+        let open = text.find('{')?;
+        let namespace = &text[1..open];
+        let close = open + text[open..].find('}')?;
+        let name = &text[open + 1..close];
+        let consumed = close + 1;
+
+        let replacement = match namespace {
+            "" => match name {
+                "sourceDir" => self.source_dir.to_string_lossy().into_owned(),
+                "sourceParentDir" => self
+                    .source_dir
+                    .parent()
+                    .map(|p| p.to_string_lossy().into_owned())
+                    .unwrap_or_default(),
+                "sourceDirName" => self
+                    .source_dir
+                    .file_name()
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .unwrap_or_default(),
+                "fileDir" => self.file_dir.to_string_lossy().into_owned(),
+                "presetName" => self.preset_name.to_string(),
+                "generator" => self.generator.to_string(),
+                "hostSystemName" => host_system_name().to_string(),
+                "dollar" => "$".to_string(),
+                "pathListSep" => if cfg!(windows) { ";" } else { ":" }.to_string(),
+                _ => return None,
+            },
+            "env" => match self.environment.get(name) {
+                Some(value) if depth < MAX_MACRO_DEPTH => {
+                    self.expand_with_depth(value, depth + 1)
+                }
+                Some(_) => String::new(),
+                None => std::env::var(name).unwrap_or_default(),
+            },
+            "penv" => std::env::var(name).unwrap_or_default(),
+            _ => return None,
+        };
+        Some((replacement, consumed))
+    }
+}
+
+// The value CMake uses for `${hostSystemName}` (`CMAKE_HOST_SYSTEM_NAME`).
+fn host_system_name() -> &'static str {
+    match std::env::consts::OS {
+        "linux" => "Linux",
+        "macos" => "Darwin",
+        "windows" => "Windows",
+        "freebsd" => "FreeBSD",
+        other => other,
+    }
+}
+
+// Index of the quote closing the JSON string that opens at `start`.
+fn string_end(bytes: &[u8], start: usize) -> usize {
+    let mut i = start + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'"' => return i,
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
+// Byte offsets of the `[` and `]` of the root object's `"key": [...]` member.
+fn find_top_level_array(content: &str, key: &str) -> Option<(usize, usize)> {
+    let bytes = content.as_bytes();
+    let mut depth = 0usize;
+    let mut open = None;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'"' => {
+                let end = string_end(bytes, i);
+                if depth == 1 && open.is_none() && content.get(i + 1..end) == Some(key) {
+                    let after = content[end + 1..].trim_start();
+                    if let Some(value) = after.strip_prefix(':').map(str::trim_start)
+                        && value.starts_with('[')
+                    {
+                        open = Some(content.len() - value.len());
+                    }
+                }
+                i = end;
+            }
+            b'{' | b'[' => depth += 1,
+            b'}' | b']' => {
+                depth = depth.saturating_sub(1);
+                if let Some(open) = open
+                    && i > open
+                    && depth == 1
+                {
+                    return Some((open, i));
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+// Leading whitespace of the line containing byte offset `pos`.
+fn line_indent(content: &str, pos: usize) -> &str {
+    let line_start = content[..pos].rfind('\n').map(|i| i + 1).unwrap_or(0);
+    let line = &content[line_start..];
+    &line[..line.len() - line.trim_start().len()]
+}
+
+// `{ "name": "dev", "configurePreset": "dev" }`, the style of single-line arrays.
+fn inline_json(value: &Value) -> String {
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            let members: Vec<String> = map
+                .iter()
+                .map(|(k, v)| format!("{}: {}", Value::String(k.clone()), inline_json(v)))
+                .collect();
+            format!("{{ {} }}", members.join(", "))
+        }
+        Value::Array(items) if !items.is_empty() => {
+            let items: Vec<String> = items.iter().map(inline_json).collect();
+            format!("[ {} ]", items.join(", "))
+        }
+        other => other.to_string(),
+    }
+}
+
+fn pretty_json(value: &Value, indent_unit: &str, base_indent: &str) -> String {
+    let mut buffer = Vec::new();
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(indent_unit.as_bytes());
+    let mut serializer = serde_json::Serializer::with_formatter(&mut buffer, formatter);
+    value
+        .serialize(&mut serializer)
+        .expect("serializing a JSON value cannot fail");
+    String::from_utf8(buffer)
+        .expect("serde_json writes UTF-8")
+        .replace('\n', &format!("\n{}", base_indent))
+}
+
+// Indentation of the first indented line, the unit the file is written with.
+fn indent_unit(content: &str) -> &str {
+    content
+        .lines()
+        .map(|line| &line[..line.len() - line.trim_start().len()])
+        .find(|indent| !indent.is_empty())
+        .unwrap_or("    ")
+}
+
+/// Appends `element` to the root object's `key` array in a presets file,
+/// creating the array if needed. The rest of the text is left as it is.
+pub fn append_to_array(content: &str, key: &str, element: &Value) -> Result<String> {
+    // Work on LF line endings and write CRLF files back the way they came.
+    if content.contains("\r\n") {
+        let updated = append_to_array(&content.replace("\r\n", "\n"), key, element)?;
+        return Ok(updated.replace('\n', "\r\n"));
+    }
+    let Some(root_close) = content.rfind('}') else {
+        bail!("Presets file is not a JSON object");
+    };
+
+    let updated = match find_top_level_array(content, key) {
+        Some((open, close)) => {
+            let key_indent = line_indent(content, open);
+            let inner = &content[open + 1..close];
+            let last_element_end = open + 1 + inner.trim_end().len();
+            if !inner.contains('\n') && !inner.trim().is_empty() {
+                // Single-line array, e.g. `[ { "name": "dev", ... } ]`
+                format!(
+                    "{}, {}{}",
+                    &content[..last_element_end],
+                    inline_json(element),
+                    &content[last_element_end..]
+                )
+            } else if inner.trim().is_empty() {
+                let indent_unit = if key_indent.is_empty() { indent_unit(content) } else { key_indent };
+                let element_indent = format!("{}{}", key_indent, indent_unit);
+                format!(
+                    "{}\n{}{}\n{}{}",
+                    &content[..open + 1],
+                    element_indent,
+                    pretty_json(element, indent_unit, &element_indent),
+                    key_indent,
+                    &content[close..]
+                )
+            } else {
+                let first_element = open + 1 + (inner.len() - inner.trim_start().len());
+                let element_indent = line_indent(content, first_element);
+                let indent_unit = element_indent
+                    .strip_prefix(key_indent)
+                    .filter(|unit| !unit.is_empty())
+                    .unwrap_or("    ");
+                // Arrays written one element per line keep that style.
+                let first_line = content[first_element..].lines().next().unwrap_or("");
+                let rendered = if first_line.matches('{').count() == first_line.matches('}').count() {
+                    inline_json(element)
+                } else {
+                    pretty_json(element, indent_unit, element_indent)
+                };
+                format!(
+                    "{},\n{}{}{}",
+                    &content[..last_element_end],
+                    element_indent,
+                    rendered,
+                    &content[last_element_end..]
+                )
+            }
+        }
+        None => {
+            let body_end = content[..root_close].trim_end().len();
+            let separator = if content[..body_end].ends_with('{') { "" } else { "," };
+            let unit = indent_unit(content);
+            let element_indent = format!("{}{}", unit, unit);
+            format!(
+                "{}{}\n{}{}: [\n{}{}\n{}]{}",
+                &content[..body_end],
+                separator,
+                unit,
+                Value::String(key.to_string()),
+                element_indent,
+                pretty_json(element, unit, &element_indent),
+                unit,
+                &content[body_end..]
+            )
+        }
+    };
+
+    serde_json::from_str::<Value>(&updated)
+        .context("Inserting the preset would produce invalid JSON")?;
+    Ok(updated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    // A fresh directory under the system temp dir holding `files`.
+    fn project(test: &str, files: &[(&str, &str)]) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("rig-presets-{}-{}", std::process::id(), test));
+        let _ = fs::remove_dir_all(&root);
+        for (path, content) in files {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        root.canonicalize().unwrap()
+    }
+
+    #[test]
+    fn resolves_inherits_chain() {
+        let root = project(
+            "inherits",
+            &[(
+                "CMakePresets.json",
+                r#"{
+    "version": 3,
+    "configurePresets": [
+        { "name": "base", "hidden": true, "generator": "Ninja", "binaryDir": "${sourceDir}/build/${presetName}",
+          "cacheVariables": { "A": "base", "B": "base", "C": "base" }, "environment": { "E": "base" } },
+        { "name": "tooling", "hidden": true, "cacheVariables": { "B": "tooling", "T": { "type": "BOOL", "value": true } } },
+        { "name": "debug", "inherits": ["tooling", "base"], "cacheVariables": { "A": "debug", "C": null },
+          "environment": { "F": "$env{E}-debug" } }
+    ]
+}"#,
+            )],
+        );
+        let presets = Presets::load(&root).unwrap();
+        let debug = presets.configure_preset("debug").unwrap();
+        assert_eq!(debug.binary_dir, root.join("build/debug"));
+        assert_eq!(debug.generator.as_deref(), Some("Ninja"));
+        assert_eq!(
+            debug.cache_variables.iter().map(|(k, v)| format!("{}={}", k, v)).collect::<Vec<_>>(),
+            ["A=debug", "B=tooling", "T=TRUE"]
+        );
+        assert_eq!(debug.environment["F"], "base-debug");
+        assert!(presets.configure_preset("base").is_err());
+        assert!(presets.resolve("base").is_ok());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn reports_inherits_cycles() {
+        let root = project(
+            "cycle",
+            &[(
+                "CMakePresets.json",
+                r#"{ "version": 3, "configurePresets": [
+                    { "name": "a", "inherits": "b", "binaryDir": "x" },
+                    { "name": "b", "inherits": "a" } ] }"#,
+            )],
+        );
+        let error = Presets::load(&root).unwrap().configure_preset("a").unwrap_err();
+        assert!(error.to_string().contains("a -> b -> a"), "{}", error);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn file_dir_is_the_declaring_file() {
+        let root = project(
+            "file-dir",
+            &[
+                (
+                    "CMakePresets.json",
+                    r#"{ "version": 4, "include": ["cmake/presets/base.json"],
+                         "configurePresets": [
+                             { "name": "dev", "inherits": "base", "installDir": "${fileDir}/install" } ] }"#,
+                ),
+                (
+                    "cmake/presets/base.json",
+                    r#"{ "version": 4, "configurePresets": [
+                        { "name": "base", "hidden": true, "binaryDir": "${fileDir}/out/${presetName}",
+                          "cacheVariables": { "TOOLCHAIN": { "type": "FILEPATH", "value": "${fileDir}/toolchain.cmake" } } } ] }"#,
+                ),
+            ],
+        );
+        let presets = Presets::load(&root).unwrap();
+        assert_eq!(
+            presets.files(),
+            [root.join("cmake/presets/base.json"), root.join("CMakePresets.json")]
+        );
+        let dev = presets.configure_preset("dev").unwrap();
+        assert_eq!(dev.binary_dir, root.join("cmake/presets/out/dev"));
+        assert_eq!(dev.install_dir, Some(root.join("install")));
+        assert_eq!(
+            dev.cache_variables["TOOLCHAIN"],
+            root.join("cmake/presets/toolchain.cmake").to_string_lossy()
+        );
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn expands_macros() {
+        let root = project(
+            "macros",
+            &[(
+                "CMakeUserPresets.json",
+                r#"{ "version": 3, "configurePresets": [
+                    { "name": "dev", "generator": "Ninja", "binaryDir": "build/${presetName}",
+                      "cacheVariables": {
+                          "DIR": "${sourceDirName}|${generator}|${dollar}|$unknown{x}|${nope}",
+                          "NESTED": "$env{OUTER}"
+                      },
+                      "environment": { "OUTER": "[$env{INNER}]", "INNER": "inner", "SELF": "$env{SELF}" } } ] }"#,
+            )],
+        );
+        let dev = Presets::load(&root).unwrap().configure_preset("dev").unwrap();
+        let name = root.file_name().unwrap().to_string_lossy();
+        assert_eq!(dev.binary_dir, root.join("build/dev"));
+        assert_eq!(
+            dev.cache_variables["DIR"],
+            format!("{}|Ninja|$|$unknown{{x}}|${{nope}}", name)
+        );
+        assert_eq!(dev.cache_variables["NESTED"], "[inner]");
+        // Self-references stop at the nesting limit instead of recursing forever.
+        assert_eq!(dev.environment["SELF"], "");
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    fn link(name: &str) -> Value {
+        json!({ "name": name, "configurePreset": name })
+    }
+
+    fn array_text<'a>(content: &'a str, key: &str) -> Option<&'a str> {
+        find_top_level_array(content, key).map(|(open, close)| &content[open..=close])
+    }
+
+    #[test]
+    fn skips_brackets_and_escaped_quotes_in_strings() {
+        let content = r#"{
+    "description": "uses [brackets] and \"configurePresets\": [ ] in text ]",
+    "configurePresets": [ { "name": "a]\"[b" } ]
+}"#;
+        assert_eq!(
+            array_text(content, "configurePresets"),
+            Some(r#"[ { "name": "a]\"[b" } ]"#)
+        );
+    }
+
+    #[test]
+    fn ignores_nested_key_before_top_level_one() {
+        let content = r#"{
+    "vendor": { "buildPresets": [ "nested" ] },
+    "buildPresets": [ "top" ]
+}"#;
+        assert_eq!(array_text(content, "buildPresets"), Some(r#"[ "top" ]"#));
+    }
+
+    #[test]
+    fn missing_key_is_not_found() {
+        assert_eq!(array_text(r#"{ "version": 3, "testPresets": {} }"#, "testPresets"), None);
+        assert_eq!(array_text(r#"{ "version": 3 }"#, "buildPresets"), None);
+    }
+
+    #[test]
+    fn appends_to_empty_array() {
+        let content = "{\n    \"version\": 3,\n    \"buildPresets\": []\n}\n";
+        assert_eq!(
+            append_to_array(content, "buildPresets", &json!({ "name": "dev" })).unwrap(),
+            "{\n    \"version\": 3,\n    \"buildPresets\": [\n        {\n            \"name\": \"dev\"\n        }\n    ]\n}\n"
+        );
+    }
+
+    #[test]
+    fn creates_missing_array() {
+        let content = "{\n  \"version\": 3\n}\n";
+        assert_eq!(
+            append_to_array(content, "testPresets", &json!({ "name": "dev" })).unwrap(),
+            "{\n  \"version\": 3,\n  \"testPresets\": [\n    {\n      \"name\": \"dev\"\n    }\n  ]\n}\n"
+        );
+    }
+
+    #[test]
+    fn keeps_two_space_indentation() {
+        let content = "{\n  \"configurePresets\": [\n    {\n      \"name\": \"debug\"\n    }\n  ]\n}\n";
+        assert_eq!(
+            append_to_array(content, "configurePresets", &json!({ "name": "dev" })).unwrap(),
+            "{\n  \"configurePresets\": [\n    {\n      \"name\": \"debug\"\n    },\n    {\n      \"name\": \"dev\"\n    }\n  ]\n}\n"
+        );
+    }
+
+    #[test]
+    fn keeps_four_space_one_line_elements() {
+        let content = "{\n    \"buildPresets\": [\n        { \"name\": \"debug\", \"configurePreset\": \"debug\" }\n    ]\n}\n";
+        assert_eq!(
+            append_to_array(content, "buildPresets", &link("dev")).unwrap(),
+            "{\n    \"buildPresets\": [\n        { \"name\": \"debug\", \"configurePreset\": \"debug\" },\n        { \"name\": \"dev\", \"configurePreset\": \"dev\" }\n    ]\n}\n"
+        );
+    }
+
+    #[test]
+    fn keeps_single_line_arrays_on_one_line() {
+        let content = "{ \"version\": 3, \"testPresets\": [ { \"name\": \"debug\" } ] }";
+        assert_eq!(
+            append_to_array(content, "testPresets", &json!({ "name": "dev" })).unwrap(),
+            "{ \"version\": 3, \"testPresets\": [ { \"name\": \"debug\" }, { \"name\": \"dev\" } ] }"
+        );
+    }
+
+    #[test]
+    fn keeps_crlf_line_endings() {
+        let content = "{\r\n    \"version\": 3,\r\n    \"buildPresets\": [\r\n        { \"name\": \"debug\" }\r\n    ]\r\n}\r\n";
+        let updated = append_to_array(content, "buildPresets", &json!({ "name": "dev" })).unwrap();
+        assert_eq!(
+            updated,
+            "{\r\n    \"version\": 3,\r\n    \"buildPresets\": [\r\n        { \"name\": \"debug\" },\r\n        { \"name\": \"dev\" }\r\n    ]\r\n}\r\n"
+        );
+        let created = append_to_array(content, "testPresets", &json!({ "name": "dev" })).unwrap();
+        assert!(!created.replace("\r\n", "").contains('\n'), "{:?}", created);
+    }
+}
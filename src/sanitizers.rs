@@ -0,0 +1,159 @@
+// src/sanitizers.rs
+//! Sanitizer presets and the runtime environment for instrumented binaries.
+//!
+//! `rig new --sanitizers` adds one configure/build/test preset per sanitizer,
+//! each inheriting `debug`. When `rig run` or `rig test` uses a preset built with
+//! `-fsanitize=...`, the matching `*_OPTIONS` and `*_SYMBOLIZER_PATH` variables
+//! are set for the child process unless the user already set them.
+use crate::presets::EffectiveConfigurePreset;
+use crate::toolchain;
+use std::env;
+use std::process::Command as OsCommand;
+
+/// A sanitizer that `rig new --sanitizers` can generate a preset for.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Sanitizer {
+    Asan,
+    Ubsan,
+    Tsan,
+    /// Clang only; every dependency must be instrumented as well.
+    Msan,
+}
+
+impl Sanitizer {
+    /// Name of the generated presets.
+    pub fn preset_name(self) -> &'static str {
+        match self {
+            Sanitizer::Asan => "asan",
+            Sanitizer::Ubsan => "ubsan",
+            Sanitizer::Tsan => "tsan",
+            Sanitizer::Msan => "msan",
+        }
+    }
+
+    pub fn display_name(self) -> &'static str {
+        match self {
+            Sanitizer::Asan => "AddressSanitizer",
+            Sanitizer::Ubsan => "UndefinedBehaviorSanitizer",
+            Sanitizer::Tsan => "ThreadSanitizer",
+            Sanitizer::Msan => "MemorySanitizer",
+        }
+    }
+
+    // The value of `-fsanitize=`.
+    fn fsanitize_value(self) -> &'static str {
+        match self {
+            Sanitizer::Asan => "address",
+            Sanitizer::Ubsan => "undefined",
+            Sanitizer::Tsan => "thread",
+            Sanitizer::Msan => "memory",
+        }
+    }
+
+    /// Flags for `CMAKE_C_FLAGS` / `CMAKE_CXX_FLAGS`.
+    pub fn compile_flags(self) -> String {
+        let extra = match self {
+            Sanitizer::Msan => " -fsanitize-memory-track-origins=2",
+            _ => "",
+        };
+        format!(
+            "-fsanitize={}{} -fno-omit-frame-pointer",
+            self.fsanitize_value(),
+            extra
+        )
+    }
+
+    /// Flags for `CMAKE_EXE_LINKER_FLAGS` / `CMAKE_SHARED_LINKER_FLAGS`.
+    pub fn link_flags(self) -> String {
+        format!("-fsanitize={}", self.fsanitize_value())
+    }
+
+    // Prefix of the runtime's environment variables, e.g. `ASAN` for `ASAN_OPTIONS`.
+    fn env_prefix(self) -> &'static str {
+        match self {
+            Sanitizer::Asan => "ASAN",
+            Sanitizer::Ubsan => "UBSAN",
+            Sanitizer::Tsan => "TSAN",
+            Sanitizer::Msan => "MSAN",
+        }
+    }
+
+    // Defaults that make reports fatal and as informative as possible.
+    fn default_options(self) -> &'static str {
+        match self {
+            Sanitizer::Asan => {
+                "detect_leaks=1:check_initialization_order=1:strict_init_order=1:detect_stack_use_after_return=1"
+            }
+            Sanitizer::Ubsan => "print_stacktrace=1:halt_on_error=1",
+            Sanitizer::Tsan => "second_deadlock_stack=1:halt_on_error=1",
+            Sanitizer::Msan => "poison_in_dtor=1:halt_on_error=1",
+        }
+    }
+}
+
+/// The sanitizers a preset builds with, read from the `-fsanitize=` flags in its
+/// cache variables. Works for hand-written presets as well as generated ones.
+pub fn preset_sanitizers(preset: &EffectiveConfigurePreset) -> Vec<Sanitizer> {
+    const FLAG_VARIABLES: [&str; 4] = [
+        "CMAKE_C_FLAGS",
+        "CMAKE_CXX_FLAGS",
+        "CMAKE_EXE_LINKER_FLAGS",
+        "CMAKE_SHARED_LINKER_FLAGS",
+    ];
+    let mut sanitizers = Vec::new();
+    let values = FLAG_VARIABLES
+        .iter()
+        .filter_map(|name| preset.cache_variables.get(*name))
+        .flat_map(|flags| flags.split_whitespace())
+        .filter_map(|flag| {
+            flag.strip_prefix("-fsanitize=")
+                .or_else(|| flag.strip_prefix("/fsanitize="))
+        })
+        .flat_map(|list| list.split(','));
+    for value in values {
+        let sanitizer = match value {
+            "address" => Sanitizer::Asan,
+            "undefined" => Sanitizer::Ubsan,
+            "thread" => Sanitizer::Tsan,
+            "memory" => Sanitizer::Msan,
+            _ => continue,
+        };
+        if !sanitizers.contains(&sanitizer) {
+            sanitizers.push(sanitizer);
+        }
+    }
+    sanitizers
+}
+
+/// Sets the sanitizer runtime variables for a command that runs binaries built
+/// with `preset`. Variables already present in the environment are left alone.
+pub fn configure_runtime(command: &mut OsCommand, preset: &EffectiveConfigurePreset) {
+    let sanitizers = preset_sanitizers(preset);
+    if sanitizers.is_empty() {
+        return;
+    }
+    let symbolizer = toolchain::find_versioned_program("llvm-symbolizer");
+    if symbolizer.is_none() {
+        println!("Note: llvm-symbolizer was not found on PATH; sanitizer stack traces may lack symbols.");
+    }
+
+    for sanitizer in sanitizers {
+        let mut variables = vec![(
+            format!("{}_OPTIONS", sanitizer.env_prefix()),
+            sanitizer.default_options().to_string(),
+        )];
+        if let Some(symbolizer) = &symbolizer {
+            variables.push((
+                format!("{}_SYMBOLIZER_PATH", sanitizer.env_prefix()),
+                symbolizer.to_string_lossy().into_owned(),
+            ));
+        }
+        for (name, value) in variables {
+            if env::var_os(&name).is_some() {
+                continue;
+            }
+            println!("Sanitizer runtime: {}={}", name, value);
+            command.env(name, value);
+        }
+    }
+}
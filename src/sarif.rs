@@ -0,0 +1,407 @@
+// src/sarif.rs
+//! Writes diagnostics as a [SARIF 2.1.0](https://docs.oasis-open.org/sarif/sarif/v2.1.0/sarif-v2.1.0.html) log.
+//!
+//! Rules are keyed by the warning flag or clang-tidy check behind each diagnostic,
+//! and locations inside the project are relative to the `PROJECTROOT` base URI.
+//! Runs from SARIF files that GCC writes with `-fdiagnostics-format=sarif-file`
+//! can be appended to the log as they are.
+use crate::diagnostics::Diagnostic;
+use crate::utils;
+use anyhow::{Context, Result};
+use serde_json::{json, Map, Value};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const PROJECT_ROOT_BASE_ID: &str = "PROJECTROOT";
+
+// Percent-encodes the characters that cannot appear in a URI path.
+fn encode_uri_path(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' | b':' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+fn file_uri(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
+    if path.starts_with('/') {
+        format!("file://{}", encode_uri_path(&path))
+    } else {
+        format!("file:///{}", encode_uri_path(&path))
+    }
+}
+
+// Resolves `.` and `..` without touching the file system, so paths of deleted
+// or generated files still work.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+/// What the column numbers of compiler diagnostics count.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColumnUnit {
+    /// Bytes: Clang, clang-tidy and GCC before 11.
+    Byte,
+    /// Display columns, with tabs expanded to multiples of 8 and wide
+    /// characters counted twice: GCC 11 and later.
+    Display,
+}
+
+impl ColumnUnit {
+    /// The unit used by a compiler, from CMake's `CMAKE_CXX_COMPILER_ID` and
+    /// `CMAKE_CXX_COMPILER_VERSION`.
+    pub fn for_compiler(id: &str, version: &str) -> Self {
+        let major = version.split('.').next().and_then(|major| major.parse::<u32>().ok());
+        if id == "GNU" && major.is_some_and(|major| major >= 11) {
+            ColumnUnit::Display
+        } else {
+            ColumnUnit::Byte
+        }
+    }
+}
+
+// Columns a character takes in GCC's display column count. Wide East Asian
+// characters and emoji take two, combining marks none.
+fn display_width(c: char, column: u32) -> u32 {
+    const TAB_STOP: u32 = 8;
+    match c {
+        '\t' => TAB_STOP - column % TAB_STOP,
+        '\u{300}'..='\u{36f}' | '\u{200b}'..='\u{200f}' => 0,
+        '\u{1100}'..='\u{115f}'
+        | '\u{2e80}'..='\u{a4cf}'
+        | '\u{ac00}'..='\u{d7a3}'
+        | '\u{f900}'..='\u{faff}'
+        | '\u{fe30}'..='\u{fe4f}'
+        | '\u{ff00}'..='\u{ff60}'
+        | '\u{ffe0}'..='\u{ffe6}'
+        | '\u{1f300}'..='\u{1f64f}'
+        | '\u{1f900}'..='\u{1f9ff}'
+        | '\u{20000}'..='\u{3fffd}' => 2,
+        _ => 1,
+    }
+}
+
+// Converts a 1-based column in `unit` on `line` to the 1-based UTF-16 code
+// unit column SARIF expects. Columns past the end of the line stay that far past it.
+fn utf16_column(line: &str, column: u32, unit: ColumnUnit) -> u32 {
+    let target = column.saturating_sub(1);
+    let mut counted = 0;
+    let mut utf16 = 0;
+    for c in line.chars() {
+        if counted >= target {
+            return utf16 + 1;
+        }
+        counted += match unit {
+            ColumnUnit::Byte => c.len_utf8() as u32,
+            ColumnUnit::Display => display_width(c, counted),
+        };
+        utf16 += c.len_utf16() as u32;
+    }
+    utf16 + target.saturating_sub(counted) + 1
+}
+
+/// Converts diagnostics into SARIF results, with paths relative to the project root.
+struct LogBuilder<'a> {
+    project_root: PathBuf,
+    /// Relative diagnostic paths are relative to the directory the compiler ran in.
+    working_dir: &'a Path,
+    column_unit: ColumnUnit,
+    /// Lines of the source files read so far, for column conversion.
+    sources: RefCell<HashMap<PathBuf, Option<Vec<String>>>>,
+    rules: Vec<String>,
+}
+
+impl LogBuilder<'_> {
+    // `column` on `line` of `file` in UTF-16 code units. Unreadable files keep
+    // the compiler's column.
+    fn column(&self, file: &str, line: u32, column: u32) -> u32 {
+        let path = self.working_dir.join(file);
+        let mut sources = self.sources.borrow_mut();
+        let lines = sources.entry(path).or_insert_with_key(|path| {
+            fs::read(path)
+                .ok()
+                .map(|content| String::from_utf8_lossy(&content).lines().map(str::to_string).collect())
+        });
+        match lines.as_ref().and_then(|lines| lines.get(line.checked_sub(1)? as usize)) {
+            Some(text) => utf16_column(text, column, self.column_unit),
+            None => column,
+        }
+    }
+
+    fn artifact_location(&self, file: &str) -> Value {
+        let path = self.working_dir.join(file);
+        // Canonical like the project root, so symlinked directories still match.
+        let path = path.canonicalize().unwrap_or_else(|_| normalize(&path));
+        match path.strip_prefix(&self.project_root) {
+            Ok(relative) => json!({
+                "uri": encode_uri_path(&relative.to_string_lossy().replace('\\', "/")),
+                "uriBaseId": PROJECT_ROOT_BASE_ID,
+            }),
+            Err(_) => json!({ "uri": file_uri(&path) }),
+        }
+    }
+
+    fn location(&self, diagnostic: &Diagnostic) -> Option<Value> {
+        let file = diagnostic.file.as_deref()?;
+        let mut physical = Map::new();
+        physical.insert("artifactLocation".to_string(), self.artifact_location(file));
+        if let Some(line) = diagnostic.line {
+            let mut region = Map::new();
+            region.insert("startLine".to_string(), json!(line));
+            if let Some(column) = diagnostic.column {
+                region.insert("startColumn".to_string(), json!(self.column(file, line, column)));
+            }
+            physical.insert("region".to_string(), Value::Object(region));
+        }
+        Some(json!({ "physicalLocation": physical }))
+    }
+
+    fn result(&mut self, diagnostic: &Diagnostic) -> Value {
+        // SARIF levels have the same names.
+        let level = diagnostic.severity.to_string();
+        let mut result = Map::new();
+        if let Some(option) = &diagnostic.option {
+            let index = match self.rules.iter().position(|r| r == option) {
+                Some(index) => index,
+                None => {
+                    self.rules.push(option.clone());
+                    self.rules.len() - 1
+                }
+            };
+            result.insert("ruleId".to_string(), json!(option));
+            result.insert("ruleIndex".to_string(), json!(index));
+        }
+        result.insert("level".to_string(), json!(level));
+        result.insert("message".to_string(), json!({ "text": diagnostic.message }));
+        if let Some(location) = self.location(diagnostic) {
+            result.insert("locations".to_string(), json!([location]));
+        }
+
+        let related: Vec<Value> = diagnostic
+            .notes
+            .iter()
+            .filter_map(|note| {
+                let mut location = self.location(note)?;
+                location["message"] = json!({ "text": note.message });
+                Some(location)
+            })
+            .collect();
+        if !related.is_empty() {
+            result.insert("relatedLocations".to_string(), Value::Array(related));
+        }
+
+        let fixes: Vec<Value> = diagnostic
+            .fixits
+            .iter()
+            .map(|fixit| {
+                json!({
+                    "artifactChanges": [{
+                        "artifactLocation": self.artifact_location(&fixit.file),
+                        "replacements": [{
+                            "deletedRegion": {
+                                "startLine": fixit.start_line,
+                                "startColumn": self.column(&fixit.file, fixit.start_line, fixit.start_column),
+                                "endLine": fixit.end_line,
+                                "endColumn": self.column(&fixit.file, fixit.end_line, fixit.end_column),
+                            },
+                            "insertedContent": { "text": fixit.replacement },
+                        }],
+                    }],
+                })
+            })
+            .collect();
+        if !fixes.is_empty() {
+            result.insert("fixes".to_string(), Value::Array(fixes));
+        }
+        Value::Object(result)
+    }
+}
+
+/// The runs of the SARIF files GCC wrote under `build_dir` since `since`.
+/// Files that cannot be read or parsed are skipped.
+pub fn gcc_sarif_runs(build_dir: &Path, since: SystemTime) -> Vec<Value> {
+    // File system timestamps are coarser than the clock; allow some slack.
+    let since = since.checked_sub(Duration::from_secs(1)).unwrap_or(since);
+    let mut runs = Vec::new();
+    let mut pending = vec![build_dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if metadata.is_dir() {
+                pending.push(path);
+                continue;
+            }
+            let fresh = metadata.modified().is_ok_and(|modified| modified >= since);
+            if !fresh || path.extension().is_none_or(|ext| ext != "sarif") {
+                continue;
+            }
+            let log = fs::read_to_string(&path)
+                .ok()
+                .and_then(|content| serde_json::from_str::<Value>(&content).ok());
+            if let Some(Value::Array(file_runs)) = log.and_then(|mut log| log.get_mut("runs").map(Value::take)) {
+                runs.extend(file_runs);
+            }
+        }
+    }
+    runs
+}
+
+/// Writes `diagnostics` (reported by `tool_name`, run in `working_dir`, with
+/// columns counted in `column_unit`) and `extra_runs` as a SARIF log at `path`.
+pub fn write_log(
+    path: &Path,
+    project_root: &Path,
+    working_dir: &Path,
+    tool_name: &str,
+    column_unit: ColumnUnit,
+    diagnostics: &[Diagnostic],
+    extra_runs: Vec<Value>,
+) -> Result<()> {
+    let project_root = project_root
+        .canonicalize()
+        .unwrap_or_else(|_| project_root.to_path_buf());
+    let mut builder = LogBuilder {
+        project_root,
+        working_dir,
+        column_unit,
+        sources: RefCell::new(HashMap::new()),
+        rules: Vec::new(),
+    };
+    let results: Vec<Value> = diagnostics.iter().map(|d| builder.result(d)).collect();
+    let rules: Vec<Value> = builder
+        .rules
+        .iter()
+        .map(|rule| {
+            let description = if rule.starts_with("-W") {
+                format!("Compiler warning {}", rule)
+            } else {
+                format!("clang-tidy check {}", rule)
+            };
+            json!({ "id": rule, "shortDescription": { "text": description } })
+        })
+        .collect();
+
+    // The base URI of a directory needs a trailing slash.
+    let mut root_uri = file_uri(&builder.project_root);
+    if !root_uri.ends_with('/') {
+        root_uri.push('/');
+    }
+    let mut runs = vec![json!({
+        "tool": {
+            "driver": {
+                "name": tool_name,
+                "rules": rules,
+            }
+        },
+        // Compiler columns are converted from bytes or display columns.
+        "columnKind": "utf16CodeUnits",
+        "originalUriBaseIds": {
+            PROJECT_ROOT_BASE_ID: { "uri": root_uri }
+        },
+        "results": results,
+    })];
+    runs.extend(extra_runs);
+
+    let log = json!({
+        "$schema": SARIF_SCHEMA,
+        "version": "2.1.0",
+        "runs": runs,
+    });
+    let content = serde_json::to_string_pretty(&log).context("Failed to serialize the SARIF log")?;
+    utils::write(path, content).with_context(|| format!("Failed to write SARIF log {:?}", path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 1-based byte column of the first `x` in `line`.
+    fn byte_column(line: &str) -> u32 {
+        line.find('x').unwrap() as u32 + 1
+    }
+
+    #[test]
+    fn picks_column_unit_from_compiler() {
+        assert_eq!(ColumnUnit::for_compiler("GNU", "13.2.0"), ColumnUnit::Display);
+        assert_eq!(ColumnUnit::for_compiler("GNU", "11.1.0"), ColumnUnit::Display);
+        assert_eq!(ColumnUnit::for_compiler("GNU", "10.5.0"), ColumnUnit::Byte);
+        assert_eq!(ColumnUnit::for_compiler("Clang", "18.1.3"), ColumnUnit::Byte);
+        assert_eq!(ColumnUnit::for_compiler("AppleClang", "15.0.0"), ColumnUnit::Byte);
+    }
+
+    #[test]
+    fn converts_byte_columns() {
+        let ascii = "int x = 0;";
+        assert_eq!(utf16_column(ascii, byte_column(ascii), ColumnUnit::Byte), 5);
+
+        // `é` is 2 bytes and 1 UTF-16 unit, `😀` 4 bytes and 2 units.
+        let line = "s = \"é😀\"; x";
+        assert_eq!(utf16_column(line, byte_column(line), ColumnUnit::Byte), 12);
+        assert_eq!(line.encode_utf16().position(|u| u == u16::from(b'x')), Some(11));
+
+        // Past the end of the line.
+        assert_eq!(utf16_column("é", 5, ColumnUnit::Byte), 4);
+    }
+
+    #[test]
+    fn converts_display_columns() {
+        // The tab fills columns 1-8, `é` takes one column, `漢` and `😀` two each.
+        let line = "\té漢😀x";
+        assert_eq!(utf16_column(line, 14, ColumnUnit::Display), 6);
+        assert_eq!(utf16_column("ab\tx", 9, ColumnUnit::Display), 4);
+        assert_eq!(utf16_column("int x;", 5, ColumnUnit::Display), 5);
+    }
+
+    #[test]
+    fn log_uses_utf16_columns() {
+        let root = std::env::temp_dir().join(format!("rig-sarif-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("main.cc"), "auto s = \"😀\"; int x;\n").unwrap();
+        let diagnostic = Diagnostic {
+            file: Some("main.cc".to_string()),
+            line: Some(1),
+            column: Some(21),
+            severity: crate::diagnostics::Severity::Warning,
+            message: "unused variable 'x'".to_string(),
+            option: Some("-Wunused-variable".to_string()),
+            notes: Vec::new(),
+            fixits: Vec::new(),
+        };
+        let path = root.join("log.sarif");
+        write_log(&path, &root, &root, "rig", ColumnUnit::Byte, &[diagnostic], Vec::new()).unwrap();
+        let log: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        fs::remove_dir_all(&root).unwrap();
+
+        let run = &log["runs"][0];
+        assert_eq!(run["columnKind"], "utf16CodeUnits");
+        let region = &run["results"][0]["locations"][0]["physicalLocation"]["region"];
+        assert_eq!(region["startColumn"], 19);
+        assert_eq!(run["results"][0]["locations"][0]["physicalLocation"]["artifactLocation"]["uri"], "main.cc");
+    }
+}
@@ -0,0 +1,265 @@
+// src/toolchain.rs
+//! Detection of the C/C++ compilers and build tools installed on the machine.
+//!
+//! Compilers are found by scanning `PATH` for `gcc`, `gcc-N`, `clang`, `clang-N`
+//! and `cc` (plus `cl` on Windows) that have a matching C++ driver next to them.
+//! The family and version come from the compiler's `--version` output.
+use anyhow::{bail, Result};
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command as OsCommand;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompilerFamily {
+    Gcc,
+    Clang,
+    Msvc,
+    Unknown,
+}
+
+impl fmt::Display for CompilerFamily {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            CompilerFamily::Gcc => "gcc",
+            CompilerFamily::Clang => "clang",
+            CompilerFamily::Msvc => "msvc",
+            CompilerFamily::Unknown => "unknown",
+        };
+        f.write_str(name)
+    }
+}
+
+/// A C/C++ compiler pair found on `PATH`.
+#[derive(Debug, Clone)]
+pub struct Compiler {
+    /// The name used in rig.toml and on the command line, e.g. `gcc-13`.
+    pub name: String,
+    /// Executable names written to `CMAKE_C_COMPILER` / `CMAKE_CXX_COMPILER`.
+    pub c: String,
+    pub cxx: String,
+    pub family: CompilerFamily,
+    pub version: Option<String>,
+    /// Where the C compiler was found.
+    pub path: PathBuf,
+}
+
+// Preferred compilers when none is requested, in order.
+const PREFERRED_COMPILERS: &[&str] = &["cl", "clang", "gcc", "cc"];
+
+/// Maps a C compiler name to its C++ counterpart, e.g. `gcc-13` -> `g++-13`.
+fn cxx_counterpart(c: &str) -> Option<String> {
+    if c == "cl" {
+        return Some("cl".to_string());
+    }
+    if c == "cc" {
+        return Some("c++".to_string());
+    }
+    let (prefix, cxx_prefix) = if c.starts_with("gcc") {
+        ("gcc", "g++")
+    } else if c.starts_with("clang") {
+        ("clang", "clang++")
+    } else {
+        return None;
+    };
+    let suffix = &c[prefix.len()..];
+    // Only plain or version-suffixed drivers: `gcc-13`, not `gcc-ar` or `clang-format`.
+    let version_suffix = suffix
+        .strip_prefix('-')
+        .is_some_and(|v| !v.is_empty() && v.chars().all(|ch| ch.is_ascii_digit() || ch == '.'));
+    if !suffix.is_empty() && !version_suffix {
+        return None;
+    }
+    Some(format!("{}{}", cxx_prefix, suffix))
+}
+
+fn is_executable(path: &Path) -> bool {
+    let Ok(metadata) = fs::metadata(path) else {
+        return false;
+    };
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        metadata.is_file() && metadata.permissions().mode() & 0o111 != 0
+    }
+    #[cfg(not(unix))]
+    {
+        metadata.is_file()
+    }
+}
+
+/// Finds `program` (without the executable suffix) on `PATH`.
+pub fn find_program(program: &str) -> Option<PathBuf> {
+    let path_var = env::var_os("PATH")?;
+    env::split_paths(&path_var)
+        .map(|dir| dir.join(format!("{}{}", program, env::consts::EXE_SUFFIX)))
+        .find(|candidate| is_executable(candidate))
+}
+
+/// Finds `program` on `PATH`, or else its newest version-suffixed variant
+/// (`llvm-symbolizer-18` for `llvm-symbolizer`), as installed by distributions
+/// that ship several LLVM versions side by side.
+pub fn find_versioned_program(program: &str) -> Option<PathBuf> {
+    if let Some(path) = find_program(program) {
+        return Some(path);
+    }
+    let path_var = env::var_os("PATH")?;
+    let prefix = format!("{}-", program);
+    let mut best: Option<(u32, PathBuf)> = None;
+    for dir in env::split_paths(&path_var) {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let file_name = entry.file_name();
+            let Some(version) = file_name
+                .to_str()
+                .and_then(|n| n.strip_suffix(env::consts::EXE_SUFFIX))
+                .and_then(|n| n.strip_prefix(&prefix))
+                .and_then(|v| v.parse::<u32>().ok())
+            else {
+                continue;
+            };
+            if best.as_ref().is_none_or(|(b, _)| version > *b) && is_executable(&entry.path()) {
+                best = Some((version, entry.path()));
+            }
+        }
+    }
+    best.map(|(_, path)| path)
+}
+
+// The version number in a `--version` banner: the word after "version"
+// (clang), or else the last version-looking word of the first line (gcc).
+fn parse_version(output: &str) -> Option<String> {
+    let first_line = output.lines().next()?;
+    let looks_like_version = |word: &str| {
+        let mut parts = word.split('.');
+        word.contains('.')
+            && parts.all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()))
+    };
+    let words: Vec<&str> = first_line.split_whitespace().collect();
+    if let Some(pos) = words.iter().position(|w| w.eq_ignore_ascii_case("version"))
+        && let Some(word) = words.get(pos + 1)
+        && looks_like_version(word)
+    {
+        return Some(word.to_string());
+    }
+    words
+        .iter()
+        .rev()
+        .find(|w| looks_like_version(w))
+        .map(|w| w.to_string())
+}
+
+fn probe(name: &str, path: &Path) -> Compiler {
+    let cxx = cxx_counterpart(name).unwrap_or_else(|| name.to_string());
+    if name == "cl" {
+        // cl has no --version; its banner goes to stderr when run without arguments.
+        return Compiler {
+            name: name.to_string(),
+            c: "cl.exe".to_string(),
+            cxx: "cl.exe".to_string(),
+            family: CompilerFamily::Msvc,
+            version: None,
+            path: path.to_path_buf(),
+        };
+    }
+    let output = OsCommand::new(path)
+        .arg("--version")
+        .output()
+        .map(|out| String::from_utf8_lossy(&out.stdout).into_owned())
+        .unwrap_or_default();
+    let lower = output.to_lowercase();
+    let family = if lower.contains("clang") {
+        CompilerFamily::Clang
+    } else if lower.contains("gcc") || lower.contains("free software foundation") {
+        CompilerFamily::Gcc
+    } else {
+        CompilerFamily::Unknown
+    };
+    Compiler {
+        name: name.to_string(),
+        c: name.to_string(),
+        cxx,
+        family,
+        version: parse_version(&output),
+        path: path.to_path_buf(),
+    }
+}
+
+/// All compilers on `PATH`, sorted by name. The first match on `PATH` wins for each name.
+pub fn detect_compilers() -> Vec<Compiler> {
+    let Some(path_var) = env::var_os("PATH") else {
+        return Vec::new();
+    };
+    let mut compilers: Vec<Compiler> = Vec::new();
+    for dir in env::split_paths(&path_var) {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let file_name = entry.file_name();
+            let Some(file_name) = file_name.to_str() else {
+                continue;
+            };
+            let name = file_name
+                .strip_suffix(env::consts::EXE_SUFFIX)
+                .filter(|_| !env::consts::EXE_SUFFIX.is_empty())
+                .unwrap_or(file_name);
+            if name == "cl" && !cfg!(windows) {
+                continue;
+            }
+            let Some(cxx) = cxx_counterpart(name) else {
+                continue;
+            };
+            if compilers.iter().any(|c| c.name == name) || !is_executable(&entry.path()) {
+                continue;
+            }
+            let cxx_path = dir.join(format!("{}{}", cxx, env::consts::EXE_SUFFIX));
+            if !is_executable(&cxx_path) {
+                continue;
+            }
+            compilers.push(probe(name, &entry.path()));
+        }
+    }
+    compilers.sort_by(|a, b| a.name.cmp(&b.name));
+    compilers
+}
+
+/// Picks the compiler named `requested`, or the preferred installed one.
+/// Returns `None` when no compiler was requested and none is installed.
+pub fn select_compiler(requested: Option<&str>) -> Result<Option<Compiler>> {
+    let compilers = detect_compilers();
+    if let Some(requested) = requested {
+        return match compilers.iter().find(|c| c.name == requested) {
+            Some(compiler) => Ok(Some(compiler.clone())),
+            None => {
+                let available: Vec<&str> = compilers.iter().map(|c| c.name.as_str()).collect();
+                bail!(
+                    "Compiler '{}' was not found on PATH. Detected compilers: {}",
+                    requested,
+                    if available.is_empty() {
+                        "none".to_string()
+                    } else {
+                        available.join(", ")
+                    }
+                )
+            }
+        };
+    }
+    Ok(PREFERRED_COMPILERS
+        .iter()
+        .find_map(|preferred| compilers.iter().find(|c| c.name == *preferred))
+        .or_else(|| compilers.first())
+        .cloned())
+}
+
+/// The generator used when Ninja is not installed.
+pub fn fallback_generator() -> &'static str {
+    if cfg!(windows) {
+        "NMake Makefiles"
+    } else {
+        "Unix Makefiles"
+    }
+}
@@ -0,0 +1,201 @@
+// src/usage.rs
+//! Works out which `find_package` call and imported targets a vcpkg port provides.
+//!
+//! Resolution order:
+//! 1. the built-in table of well-known exceptions below,
+//! 2. the `usage` file vcpkg installs to `vcpkg_installed/<triplet>/share/<port>/usage`,
+//! 3. the exported targets found in the port's `*Config.cmake` / `*Targets.cmake` files,
+//! 4. the conventional `<port>::<port>` guess.
+use crate::cmake::CMakePackage;
+use crate::vcpkg;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// (port, find_package arguments, targets)
+const KNOWN_PACKAGES: &[(&str, &str, &[&str])] = &[
+    ("boost", "Boost REQUIRED", &["Boost::boost"]),
+    ("catch2", "Catch2 CONFIG REQUIRED", &["Catch2::Catch2WithMain"]),
+    ("curl", "CURL CONFIG REQUIRED", &["CURL::libcurl"]),
+    ("eigen3", "Eigen3 CONFIG REQUIRED", &["Eigen3::Eigen"]),
+    ("gtest", "GTest CONFIG REQUIRED", &["GTest::gtest", "GTest::gtest_main"]),
+    ("libpng", "PNG REQUIRED", &["PNG::PNG"]),
+    ("nlohmann-json", "nlohmann_json CONFIG REQUIRED", &["nlohmann_json::nlohmann_json"]),
+    ("openssl", "OpenSSL REQUIRED", &["OpenSSL::SSL", "OpenSSL::Crypto"]),
+    ("protobuf", "protobuf CONFIG REQUIRED", &["protobuf::libprotobuf"]),
+    ("range-v3", "range-v3 CONFIG REQUIRED", &["range-v3::range-v3"]),
+    (
+        "sdl2",
+        "SDL2 CONFIG REQUIRED",
+        &[
+            "$<TARGET_NAME_IF_EXISTS:SDL2::SDL2main>",
+            "$<IF:$<TARGET_EXISTS:SDL2::SDL2>,SDL2::SDL2,SDL2::SDL2-static>",
+        ],
+    ),
+    ("sqlite3", "unofficial-sqlite3 CONFIG REQUIRED", &["unofficial::sqlite3::sqlite3"]),
+    ("zlib", "ZLIB REQUIRED", &["ZLIB::ZLIB"]),
+];
+
+/// Resolves how `port` should be consumed from the CMakeLists.txt in `project_root`.
+pub fn resolve_package(project_root: &Path, port: &str) -> CMakePackage {
+    if let Some(package) = known_package(port) {
+        return package;
+    }
+    for share_dir in installed_share_dirs(project_root, port) {
+        if let Some(package) = fs::read_to_string(share_dir.join("usage"))
+            .ok()
+            .and_then(|usage| parse_usage(&usage))
+        {
+            return package;
+        }
+        if let Some(package) = scan_config_files(&share_dir) {
+            return package;
+        }
+    }
+    CMakePackage::guess(port)
+}
+
+fn known_package(port: &str) -> Option<CMakePackage> {
+    if let Some((_, args, targets)) = KNOWN_PACKAGES.iter().find(|(name, _, _)| *name == port) {
+        return Some(CMakePackage {
+            find_package_args: args.to_string(),
+            targets: targets.iter().map(|t| t.to_string()).collect(),
+        });
+    }
+    // Individual boost libraries (boost-filesystem, ...) export boost_<lib> packages.
+    if let Some(lib) = port.strip_prefix("boost-") {
+        let lib = lib.replace('-', "_");
+        return Some(CMakePackage {
+            find_package_args: format!("boost_{} CONFIG REQUIRED", lib),
+            targets: vec![format!("Boost::{}", lib)],
+        });
+    }
+    None
+}
+
+// `share/<port>` directories of every installed triplet.
+fn installed_share_dirs(project_root: &Path, port: &str) -> Vec<PathBuf> {
+    vcpkg::installed_triplet_dirs(project_root)
+        .into_iter()
+        .map(|triplet_dir| triplet_dir.join("share").join(port))
+        .filter(|share_dir| share_dir.is_dir())
+        .collect()
+}
+
+/// Returns the argument text of each `name(...)` call in `text`, handling nested parentheses.
+fn cmake_calls<'a>(text: &'a str, name: &str) -> Vec<&'a str> {
+    let mut calls = Vec::new();
+    let pattern = format!("{}(", name);
+    let mut search_from = 0;
+    while let Some(pos) = text[search_from..].find(&pattern) {
+        let args_start = search_from + pos + pattern.len();
+        let mut depth = 1;
+        let mut args_end = None;
+        for (i, c) in text[args_start..].char_indices() {
+            match c {
+                '(' => depth += 1,
+                ')' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        args_end = Some(args_start + i);
+                        break;
+                    }
+                }
+                _ => {}
+            }
+        }
+        let Some(args_end) = args_end else {
+            break;
+        };
+        calls.push(&text[args_start..args_end]);
+        search_from = args_end;
+    }
+    calls
+}
+
+// Placeholders such as `<lib>` or `[COMPONENTS ...]` mean the usage text is a template.
+fn is_placeholder(token: &str) -> bool {
+    token.contains('[') || token.replace("$<", "").contains('<')
+}
+
+/// Parses the first `find_package` and `target_link_libraries` calls of a vcpkg usage file.
+fn parse_usage(usage: &str) -> Option<CMakePackage> {
+    let find_package_args = cmake_calls(usage, "find_package")
+        .into_iter()
+        .next()?
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    if find_package_args.is_empty() || is_placeholder(&find_package_args) {
+        return None;
+    }
+
+    let targets: Vec<String> = cmake_calls(usage, "target_link_libraries")
+        .into_iter()
+        .next()
+        .map(|args| {
+            args.split_whitespace()
+                .skip(1) // the consumer target, usually `main`
+                .filter(|t| !matches!(*t, "PRIVATE" | "PUBLIC" | "INTERFACE"))
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default();
+    if targets.iter().any(|t| is_placeholder(t)) {
+        return None;
+    }
+
+    Some(CMakePackage {
+        find_package_args,
+        targets,
+    })
+}
+
+/// Derives the package from `<Pkg>Config.cmake` / `<pkg>-config.cmake` and the
+/// `IMPORTED` targets declared in the matching targets files.
+fn scan_config_files(share_dir: &Path) -> Option<CMakePackage> {
+    let mut package_name = None;
+    let mut targets = Vec::new();
+
+    let mut files: Vec<PathBuf> = fs::read_dir(share_dir)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .collect();
+    files.sort();
+
+    for file in &files {
+        let Some(file_name) = file.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        if let Some(name) = file_name
+            .strip_suffix("Config.cmake")
+            .or_else(|| file_name.strip_suffix("-config.cmake"))
+        {
+            package_name.get_or_insert_with(|| name.to_string());
+        }
+        let lower = file_name.to_ascii_lowercase();
+        if lower.ends_with("targets.cmake") {
+            let Ok(content) = fs::read_to_string(file) else {
+                continue;
+            };
+            for args in cmake_calls(&content, "add_library") {
+                let mut words = args.split_whitespace();
+                if let Some(target) = words.next()
+                    && words.any(|w| w == "IMPORTED")
+                    && !targets.iter().any(|t| t == target)
+                {
+                    targets.push(target.to_string());
+                }
+            }
+        }
+    }
+
+    let package_name = package_name?;
+    if targets.is_empty() {
+        return None;
+    }
+    Some(CMakePackage {
+        find_package_args: format!("{} CONFIG REQUIRED", package_name),
+        targets,
+    })
+}
@@ -5,8 +5,127 @@ use std::fs;
 use std::io::{self, Write};
 use std::path::{Path, PathBuf};
 use std::process::Command as OsCommand;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+static DRY_RUN: AtomicBool = AtomicBool::new(false);
+
+/// Turns on `--dry-run`: the helpers below report the files they would change
+/// and the commands they would run instead of doing it.
+pub fn set_dry_run(enabled: bool) {
+    DRY_RUN.store(enabled, Ordering::Relaxed);
+}
+
+pub fn is_dry_run() -> bool {
+    DRY_RUN.load(Ordering::Relaxed)
+}
+
+// Quotes `arg` for a POSIX shell when it has anything but plain characters.
+fn shell_quote(arg: &str) -> String {
+    let plain = |c: char| c.is_ascii_alphanumeric() || "-_./=:,+@%^".contains(c);
+    if !arg.is_empty() && arg.chars().all(plain) {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+/// The command line of `command`, quoted so it can be pasted into a shell,
+/// followed by the directory it runs in and the environment it changes.
+pub fn describe_command(command: &OsCommand) -> String {
+    let mut line = shell_quote(&command.get_program().to_string_lossy());
+    for arg in command.get_args() {
+        line.push(' ');
+        line.push_str(&shell_quote(&arg.to_string_lossy()));
+    }
+    let dir = command
+        .get_current_dir()
+        .map(Path::to_path_buf)
+        .or_else(|| env::current_dir().ok())
+        .unwrap_or_default();
+    line.push_str(&format!("\n    in: {}", dir.display()));
+    for (key, value) in command.get_envs() {
+        let key = key.to_string_lossy();
+        match value {
+            Some(value) => line.push_str(&format!(
+                "\n    env: {}={}",
+                key,
+                shell_quote(&value.to_string_lossy())
+            )),
+            None => line.push_str(&format!("\n    env: unset {}", key)),
+        }
+    }
+    line
+}
+
+/// Under `--dry-run`, prints `command` to stderr and returns true: the caller must not run it.
+/// Dry-run notices go to stderr so they never mix with JSON output on stdout.
+pub fn skip_in_dry_run(command: &OsCommand) -> bool {
+    if is_dry_run() {
+        eprintln!("[dry-run] Would run: {}", describe_command(command));
+    }
+    is_dry_run()
+}
+
+/// `fs::write` that only reports the write under `--dry-run`.
+pub fn write(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    let (path, contents) = (path.as_ref(), contents.as_ref());
+    if is_dry_run() {
+        let action = if path.exists() { "overwrite" } else { "create" };
+        eprintln!("[dry-run] Would {} {:?} ({} bytes)", action, path, contents.len());
+        return Ok(());
+    }
+    fs::write(path, contents)
+}
+
+/// `fs::create_dir_all` that only reports missing directories under `--dry-run`.
+pub fn create_dir_all(path: impl AsRef<Path>) -> io::Result<()> {
+    let path = path.as_ref();
+    if is_dry_run() {
+        if !path.is_dir() {
+            eprintln!("[dry-run] Would create directory {:?}", path);
+        }
+        return Ok(());
+    }
+    fs::create_dir_all(path)
+}
+
+/// `fs::remove_dir_all` that only reports the removal under `--dry-run`.
+pub fn remove_dir_all(path: impl AsRef<Path>) -> io::Result<()> {
+    let path = path.as_ref();
+    if is_dry_run() {
+        eprintln!("[dry-run] Would remove directory {:?} and everything in it", path);
+        return Ok(());
+    }
+    fs::remove_dir_all(path)
+}
+
+/// `fs::remove_file` that only reports the removal under `--dry-run`.
+pub fn remove_file(path: impl AsRef<Path>) -> io::Result<()> {
+    let path = path.as_ref();
+    if is_dry_run() {
+        eprintln!("[dry-run] Would remove {:?}", path);
+        return Ok(());
+    }
+    fs::remove_file(path)
+}
+
+/// `fs::copy` that only reports the copy under `--dry-run`.
+pub fn copy(from: impl AsRef<Path>, to: impl AsRef<Path>) -> io::Result<u64> {
+    let (from, to) = (from.as_ref(), to.as_ref());
+    if is_dry_run() {
+        eprintln!("[dry-run] Would copy {:?} to {:?}", from, to);
+        return Ok(0);
+    }
+    fs::copy(from, to)
+}
 
 pub fn write_file_content(path: &Path, content: &str) -> Result<()> {
+    if is_dry_run() {
+        eprintln!("[dry-run] Would create file: {:?} ({} bytes)", path, content.len());
+        return Ok(());
+    }
     let mut file = fs::File::create(path)
         .with_context(|| format!("Failed to create file: {:?}", path))?;
     file.write_all(content.as_bytes())
@@ -33,6 +152,9 @@ pub fn run_command(
     );
     let dir_desc = current_dir.unwrap_or_else(|| Path::new("."));
 
+    if skip_in_dry_run(&cmd_instance) {
+        return Ok(());
+    }
     println!("Executing: {} (in {:?})", cmd_desc, dir_desc);
 
     let status = cmd_instance.status().with_context(|| {
@@ -53,8 +175,40 @@ pub fn run_command(
     Ok(())
 }
 
+/// Runs a command and returns its stdout (trimmed). Fails on a non-zero exit status.
+pub fn run_command_capture(
+    command_path: &Path,
+    args: &[&str],
+    current_dir: Option<&Path>,
+) -> Result<String> {
+    let mut cmd_instance = OsCommand::new(command_path);
+    cmd_instance.args(args);
+    if let Some(dir) = current_dir {
+        cmd_instance.current_dir(dir);
+    }
+
+    let cmd_desc = format!("{} {}", command_path.display(), args.join(" "));
+    let output = cmd_instance
+        .output()
+        .with_context(|| format!("Failed to execute command: {}", cmd_desc))?;
+    if !output.status.success() {
+        bail!(
+            "Command failed: {} (exit code: {:?}): {}",
+            cmd_desc,
+            output.status.code(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
 pub fn handle_project_directory_creation(project_path: &Path, project_name: &str) -> Result<()> {
-    if project_path.exists() {
+    if project_path.exists() && is_dry_run() {
+        eprintln!(
+            "[dry-run] Directory '{}' already exists; rig would ask before overwriting it.",
+            project_name
+        );
+    } else if project_path.exists() {
         print!(
             "Directory '{}' already exists. Overwrite? (y/N): ",
             project_name
@@ -68,15 +222,112 @@ pub fn handle_project_directory_creation(project_path: &Path, project_name: &str
             println!("Aborted.");
             bail!("User aborted project creation.");
         }
-        fs::remove_dir_all(project_path)
+        remove_dir_all(project_path)
             .with_context(|| format!("Failed to remove existing directory: {:?}", project_path))?;
     }
-    fs::create_dir_all(project_path)
+    create_dir_all(project_path)
         .with_context(|| format!("Failed to create project directory: {:?}", project_path))?;
-    println!("Created directory: {:?}", project_path);
+    if !is_dry_run() {
+        println!("Created directory: {:?}", project_path);
+    }
     Ok(())
 }
 
+// Directories that never contain project sources.
+const NON_SOURCE_DIRS: &[&str] = &["build", "install", "vcpkg_installed"];
+const SOURCE_EXTENSIONS: &[&str] = &[
+    "c", "cc", "cpp", "cxx", "c++", "h", "hh", "hpp", "hxx", "h++", "ipp", "inl",
+];
+
+/// Whether `path` has a C/C++ source or header extension.
+pub fn is_source_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| SOURCE_EXTENSIONS.contains(&ext))
+}
+
+/// Recursively collects the C/C++ sources and headers under `project_root`,
+/// skipping build outputs, vcpkg trees and hidden directories.
+pub fn collect_source_files(project_root: &Path) -> Result<Vec<PathBuf>> {
+    collect_project_files(project_root, is_source_file)
+}
+
+/// Recursively collects the files under `project_root` for which `include`
+/// returns true, skipping build outputs, vcpkg trees and hidden directories.
+pub fn collect_project_files(
+    project_root: &Path,
+    include: impl Fn(&Path) -> bool,
+) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending = vec![project_root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let entries =
+            fs::read_dir(&dir).with_context(|| format!("Failed to read directory: {:?}", dir))?;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            if path.is_dir() {
+                if !name.starts_with('.') && !NON_SOURCE_DIRS.contains(&name.as_ref()) {
+                    pending.push(path);
+                }
+            } else if include(&path) {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Whether `path` lies inside `project_root`, outside build outputs, vcpkg trees
+/// and hidden directories.
+pub fn is_project_source(project_root: &Path, path: &Path) -> bool {
+    let root = project_root
+        .canonicalize()
+        .unwrap_or_else(|_| project_root.to_path_buf());
+    let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let Ok(relative) = path.strip_prefix(&root) else {
+        return false;
+    };
+    !relative.parent().into_iter().flat_map(Path::components).any(|c| {
+        let name = c.as_os_str().to_string_lossy();
+        name.starts_with('.') || NON_SOURCE_DIRS.contains(&name.as_ref())
+    })
+}
+
+/// The project's C/C++ sources and headers. Inside a git work tree the list
+/// comes from `git ls-files`, so `.gitignore` is respected; otherwise the
+/// directory tree is walked with [`collect_source_files`].
+pub fn list_source_files(project_root: &Path) -> Result<Vec<PathBuf>> {
+    let output = OsCommand::new("git")
+        .args(["ls-files", "--cached", "--others", "--exclude-standard", "-z"])
+        .current_dir(project_root)
+        .output();
+    let output = match output {
+        Ok(output) if output.status.success() => output,
+        _ => return collect_source_files(project_root),
+    };
+    let mut files: Vec<PathBuf> = output
+        .stdout
+        .split(|byte| *byte == 0)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| PathBuf::from(String::from_utf8_lossy(entry).into_owned()))
+        .filter(|path| {
+            !path
+                .components()
+                .any(|c| NON_SOURCE_DIRS.contains(&c.as_os_str().to_string_lossy().as_ref()))
+        })
+        .filter(|path| is_source_file(path))
+        .map(|path| project_root.join(path))
+        // Deleted files are still listed until the deletion is committed.
+        .filter(|path| path.is_file())
+        .collect();
+    files.sort();
+    files.dedup();
+    Ok(files)
+}
+
 /// Searches upwards from the current directory for a specific marker file or directory.
 pub fn find_project_root_by_marker(marker_filename: &str) -> Result<PathBuf> {
     let mut current_dir = env::current_dir().context("Failed to get current directory")?;
@@ -92,4 +343,186 @@ pub fn find_project_root_by_marker(marker_filename: &str) -> Result<PathBuf> {
             ),
         }
     }
-}
\ No newline at end of file
+}
+
+/// Produces a unified diff (3 lines of context) between two texts.
+/// Returns an empty string when the texts are identical.
+pub fn unified_diff(old: &str, new: &str, label: &str) -> String {
+    const CONTEXT: usize = 3;
+    // Lines keep their terminator, so a missing final newline counts as a change.
+    let old_lines: Vec<&str> = old.split_inclusive('\n').collect();
+    let new_lines: Vec<&str> = new.split_inclusive('\n').collect();
+
+    // Longest-common-subsequence table over the lines.
+    let (n, m) = (old_lines.len(), new_lines.len());
+    let mut lcs = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if old_lines[i] == new_lines[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    // Walk the table into a list of (tag, old_index, new_index) edit operations.
+    let mut ops: Vec<(char, usize, usize)> = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && old_lines[i] == new_lines[j] {
+            ops.push((' ', i, j));
+            i += 1;
+            j += 1;
+        } else if i < n && (j == m || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            // Removals come before additions, as in `diff -u`.
+            ops.push(('-', i, j));
+            i += 1;
+        } else {
+            ops.push(('+', i, j));
+            j += 1;
+        }
+    }
+
+    if ops.iter().all(|(tag, _, _)| *tag == ' ') {
+        return String::new();
+    }
+
+    let mut out = format!("--- a/{}\n+++ b/{}\n", label, label);
+    let mut k = 0;
+    while k < ops.len() {
+        if ops[k].0 == ' ' {
+            k += 1;
+            continue;
+        }
+        // Extend the hunk while changes are closer than 2*CONTEXT lines apart.
+        let start = k.saturating_sub(CONTEXT);
+        let mut end = k;
+        let mut unchanged_run = 0;
+        while end < ops.len() && unchanged_run <= 2 * CONTEXT {
+            if ops[end].0 == ' ' {
+                unchanged_run += 1;
+            } else {
+                unchanged_run = 0;
+            }
+            end += 1;
+        }
+        let end = (end - unchanged_run + CONTEXT.min(unchanged_run)).min(ops.len());
+
+        let hunk = &ops[start..end];
+        let old_count = hunk.iter().filter(|(t, _, _)| *t != '+').count();
+        let new_count = hunk.iter().filter(|(t, _, _)| *t != '-').count();
+        let old_start = hunk[0].1 + usize::from(old_count > 0);
+        let new_start = hunk[0].2 + usize::from(new_count > 0);
+        out.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            old_start, old_count, new_start, new_count
+        ));
+        for (tag, oi, nj) in hunk {
+            let line = if *tag == '+' { new_lines[*nj] } else { old_lines[*oi] };
+            out.push(*tag);
+            out.push_str(line);
+            if !line.ends_with('\n') {
+                out.push_str("\n\\ No newline at end of file\n");
+            }
+        }
+        k = end;
+    }
+    out
+}
+
+/// Calls `f` on every item on up to `max_workers` threads (at most one per CPU)
+/// and returns the results in the order of `items`.
+pub fn run_parallel<T: Sync, R: Send>(
+    items: &[T],
+    max_workers: usize,
+    f: impl Fn(&T) -> R + Sync,
+) -> Vec<R> {
+    let workers = thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+        .min(max_workers)
+        .min(items.len())
+        .max(1);
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new((0..items.len()).map(|_| None).collect::<Vec<Option<R>>>());
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| {
+                loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(item) = items.get(index) else {
+                        break;
+                    };
+                    let result = f(item);
+                    results.lock().expect("worker thread panicked")[index] = Some(result);
+                }
+            });
+        }
+    });
+    results
+        .into_inner()
+        .expect("worker thread panicked")
+        .into_iter()
+        .map(|result| result.expect("every item is processed"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn numbers(lines: &[&str]) -> String {
+        lines.iter().map(|line| format!("{}\n", line)).collect()
+    }
+
+    const BASE: &[&str] = &["1", "2", "3", "4", "5", "6", "7", "8", "9", "10"];
+
+    #[test]
+    fn diff_of_identical_texts_is_empty() {
+        assert_eq!(unified_diff(&numbers(BASE), &numbers(BASE), "f"), "");
+    }
+
+    #[test]
+    fn diff_hunk_at_file_start() {
+        let new = numbers(&["one", "2", "3", "4", "5", "6", "7", "8", "9", "10"]);
+        assert_eq!(
+            unified_diff(&numbers(BASE), &new, "f"),
+            "--- a/f\n+++ b/f\n@@ -1,4 +1,4 @@\n-1\n+one\n 2\n 3\n 4\n"
+        );
+    }
+
+    #[test]
+    fn diff_hunk_at_file_end() {
+        let new = numbers(&["1", "2", "3", "4", "5", "6", "7", "8", "9", "ten"]);
+        assert_eq!(
+            unified_diff(&numbers(BASE), &new, "f"),
+            "--- a/f\n+++ b/f\n@@ -7,4 +7,4 @@\n 7\n 8\n 9\n-10\n+ten\n"
+        );
+    }
+
+    #[test]
+    fn diff_splits_distant_changes_into_hunks() {
+        let new = numbers(&["one", "2", "3", "4", "5", "6", "7", "8", "9", "ten"]);
+        assert_eq!(
+            unified_diff(&numbers(BASE), &new, "f"),
+            "--- a/f\n+++ b/f\n@@ -1,4 +1,4 @@\n-1\n+one\n 2\n 3\n 4\n@@ -7,4 +7,4 @@\n 7\n 8\n 9\n-10\n+ten\n"
+        );
+    }
+
+    #[test]
+    fn diff_marks_missing_trailing_newline() {
+        assert_eq!(
+            unified_diff("1\n2\n3", "1\n2\n3\n", "f"),
+            "--- a/f\n+++ b/f\n@@ -1,3 +1,3 @@\n 1\n 2\n-3\n\\ No newline at end of file\n+3\n"
+        );
+    }
+
+    #[test]
+    fn diff_against_empty_file() {
+        assert_eq!(
+            unified_diff("", "a\nb\n", "f"),
+            "--- a/f\n+++ b/f\n@@ -0,0 +1,2 @@\n+a\n+b\n"
+        );
+    }
+}
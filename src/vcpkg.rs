@@ -1,11 +1,18 @@
 // src/vcpkg.rs
 use crate::config::ProjectConfig;
-use crate::utils::run_command;
+use crate::manifest::VcpkgManifest;
+use crate::presets::{EffectiveConfigurePreset, Presets};
+use crate::utils::{run_command, run_command_capture};
 use anyhow::{bail, Context, Result};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
 use std::env;
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
 
 pub(crate) const VCPKG_JSON_FILENAME: &str = "vcpkg.json"; // Used by project.rs
+pub(crate) const VCPKG_INSTALLED_DIRNAME: &str = "vcpkg_installed";
+pub(crate) const VCPKG_CONFIGURATION_FILENAME: &str = "vcpkg-configuration.json";
 
 pub struct VcpkgPaths {
     pub root: PathBuf,
@@ -14,13 +21,26 @@ pub struct VcpkgPaths {
     pub toolchain: PathBuf,
 }
 
-pub fn locate_and_verify_vcpkg(vcpkg_root_override: Option<String>) -> Result<VcpkgPaths> {
+/// Locates the vcpkg checkout: `--vcpkg-root` first, then `VCPKG_ROOT`, then the
+/// `[vcpkg] root` hint from rig.toml.
+pub fn locate_and_verify_vcpkg(
+    vcpkg_root_override: Option<String>,
+    vcpkg_root_hint: Option<&str>,
+) -> Result<VcpkgPaths> {
     let vcpkg_root_path_str = match vcpkg_root_override {
         Some(path) => path,
-        None => env::var("VCPKG_ROOT")
-            .context("VCPKG_ROOT environment variable not set and --vcpkg-root not provided.")?,
+        None => match (env::var("VCPKG_ROOT"), vcpkg_root_hint) {
+            (Ok(path), _) => path,
+            (Err(_), Some(hint)) => hint.to_string(),
+            (Err(_), None) => {
+                bail!("VCPKG_ROOT environment variable not set and --vcpkg-root not provided.")
+            }
+        },
     };
-    let vcpkg_root_path = PathBuf::from(&vcpkg_root_path_str);
+    // Commands run in other directories (e.g. the new project's), so relative
+    // roots are resolved against the current one.
+    let vcpkg_root_path = std::path::absolute(&vcpkg_root_path_str)
+        .unwrap_or_else(|_| PathBuf::from(&vcpkg_root_path_str));
 
     let vcpkg_exe_name = if cfg!(windows) { "vcpkg.exe" } else { "vcpkg" };
     let vcpkg_exe_path = vcpkg_root_path.join(vcpkg_exe_name);
@@ -46,7 +66,7 @@ pub fn locate_and_verify_vcpkg(vcpkg_root_override: Option<String>) -> Result<Vc
 pub fn initialize_manifest_and_dependencies(config: &ProjectConfig) -> Result<()> {
     println!("Initializing vcpkg manifest ({})...", VCPKG_JSON_FILENAME);
     run_command(
-        &config.vcpkg_paths.exe,
+        &config.vcpkg()?.exe,
         &["new", "--application"],
         Some(&config.project_path),
     )
@@ -56,15 +76,285 @@ pub fn initialize_manifest_and_dependencies(config: &ProjectConfig) -> Result<()
         println!("Adding dependencies: {:?}", config.dependencies);
         let mut add_args: Vec<&str> = vec!["add", "port"];
         // Need to convert String to &str for the slice
-        let dep_strs: Vec<String> = config.dependencies.iter().cloned().collect();
+        let dep_strs: Vec<String> = config.dependencies.to_vec();
         let dep_refs: Vec<&str> = dep_strs.iter().map(AsRef::as_ref).collect();
 
         add_args.extend_from_slice(&dep_refs);
 
         if add_args.len() > 2 { // Ensure there are actual dependencies to add
-            run_command(&config.vcpkg_paths.exe, &add_args, Some(&config.project_path))
+            run_command(&config.vcpkg()?.exe, &add_args, Some(&config.project_path))
                 .with_context(|| format!("Failed to add vcpkg dependencies: {:?}", config.dependencies))?;
         }
     }
     Ok(())
-}
\ No newline at end of file
+}
+
+/// The `vcpkg_installed` tree the vcpkg toolchain creates for `preset`.
+pub fn preset_installed_root(project_root: &Path, preset: &EffectiveConfigurePreset) -> PathBuf {
+    match preset.cache_variables.get("VCPKG_INSTALLED_DIR") {
+        Some(dir) => project_root.join(dir),
+        None => preset.binary_dir.join(VCPKG_INSTALLED_DIRNAME),
+    }
+}
+
+/// All `vcpkg_installed` trees of a project: the one at the project root
+/// (`vcpkg install` in manifest mode) and the ones the vcpkg toolchain creates
+/// for each configure preset (`VCPKG_INSTALLED_DIR`, or inside the binaryDir).
+pub fn installed_roots(project_root: &Path) -> Vec<PathBuf> {
+    let mut roots = vec![project_root.join(VCPKG_INSTALLED_DIRNAME)];
+    if let Ok(presets) = Presets::load(project_root) {
+        let effective = presets
+            .visible_configure_presets()
+            .filter_map(|p| presets.configure_preset(&p.name).ok());
+        for preset in effective {
+            let root = preset_installed_root(project_root, &preset);
+            if !roots.contains(&root) {
+                roots.push(root);
+            }
+        }
+    }
+    roots.retain(|root| root.is_dir());
+    roots
+}
+
+/// The `<installed>/<triplet>` directories of every installed tree.
+pub fn installed_triplet_dirs(project_root: &Path) -> Vec<PathBuf> {
+    let mut triplet_dirs = Vec::new();
+    for installed_root in installed_roots(project_root) {
+        let Ok(entries) = fs::read_dir(&installed_root) else {
+            continue;
+        };
+        // `vcpkg_installed/vcpkg` holds vcpkg's own bookkeeping, not a triplet.
+        triplet_dirs.extend(
+            entries
+                .flatten()
+                .filter(|entry| entry.file_name() != "vcpkg" && entry.path().is_dir())
+                .map(|entry| entry.path()),
+        );
+    }
+    triplet_dirs
+}
+
+/// A port installed in a `vcpkg_installed` tree.
+#[derive(Clone, Debug)]
+pub struct InstalledPort {
+    pub name: String,
+    pub version: String,
+}
+
+/// The port that installed each file of `installed_root`, keyed by the path
+/// used in vcpkg's `.list` files, e.g. `x64-linux/lib/libfmt.so.10`.
+pub fn installed_file_owners(installed_root: &Path) -> HashMap<String, InstalledPort> {
+    let mut owners = HashMap::new();
+    let Ok(entries) = fs::read_dir(installed_root.join("vcpkg").join("info")) else {
+        return owners;
+    };
+    for entry in entries.flatten() {
+        let file_name = entry.file_name().to_string_lossy().into_owned();
+        // `<port>_<version>_<triplet>.list`; neither port nor triplet names contain '_'.
+        let Some((name, rest)) = file_name
+            .strip_suffix(".list")
+            .and_then(|stem| stem.split_once('_'))
+        else {
+            continue;
+        };
+        let Some((version, _triplet)) = rest.rsplit_once('_') else {
+            continue;
+        };
+        let Ok(list) = fs::read_to_string(entry.path()) else {
+            continue;
+        };
+        let port = InstalledPort {
+            name: name.to_string(),
+            version: version.to_string(),
+        };
+        for line in list.lines().filter(|line| !line.ends_with('/')) {
+            owners.insert(line.to_string(), port.clone());
+        }
+    }
+    owners
+}
+
+/// Header paths (relative to `include/`) installed by `port`, read from vcpkg's
+/// `vcpkg_installed/vcpkg/info/<port>_<version>_<triplet>.list` files.
+pub fn installed_headers(project_root: &Path, port: &str) -> Vec<String> {
+    let prefix = format!("{}_", port);
+    let mut headers = Vec::new();
+    for installed_root in installed_roots(project_root) {
+        let Ok(entries) = fs::read_dir(installed_root.join("vcpkg").join("info")) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let file_name = entry.file_name().to_string_lossy().into_owned();
+            // Port names never contain '_', so the prefix match is unambiguous.
+            if !file_name.starts_with(&prefix) || !file_name.ends_with(".list") {
+                continue;
+            }
+            let Ok(list) = fs::read_to_string(entry.path()) else {
+                continue;
+            };
+            for line in list.lines() {
+                // Entries look like `x64-linux/include/fmt/core.h`.
+                if let Some((_, header)) = line.split_once("/include/")
+                    && !header.is_empty()
+                    && !header.ends_with('/')
+                {
+                    headers.push(header.to_string());
+                }
+            }
+        }
+    }
+    headers.sort();
+    headers.dedup();
+    headers
+}
+
+/// One entry of a port's version database file (`versions/<x>-/<port>.json`).
+pub struct PortVersion {
+    pub version: String,
+    pub port_version: u32,
+}
+
+impl std::fmt::Display for PortVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.port_version == 0 {
+            write!(f, "{}", self.version)
+        } else {
+            write!(f, "{}#{}", self.version, self.port_version)
+        }
+    }
+}
+
+/// Reads `ports/<port>/vcpkg.json` from the vcpkg checkout.
+pub fn port_manifest(paths: &VcpkgPaths, port: &str) -> Result<VcpkgManifest> {
+    let port_dir = paths.root.join("ports").join(port);
+    if !port_dir.is_dir() {
+        bail!(
+            "Port '{}' not found in the vcpkg checkout at {:?}",
+            port,
+            paths.root
+        );
+    }
+    VcpkgManifest::load(&port_dir.join(VCPKG_JSON_FILENAME))
+}
+
+/// Lists the versions of `port` known to the checkout's versions database.
+pub fn port_versions(paths: &VcpkgPaths, port: &str) -> Result<Vec<PortVersion>> {
+    let first_char = port.chars().next().context("Empty port name")?;
+    let db_path = paths
+        .root
+        .join("versions")
+        .join(format!("{}-", first_char))
+        .join(format!("{}.json", port));
+    let content = fs::read_to_string(&db_path)
+        .with_context(|| format!("Failed to read version database {:?}", db_path))?;
+    let db: Value = serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse version database {:?}", db_path))?;
+
+    let entries = db
+        .get("versions")
+        .and_then(Value::as_array)
+        .with_context(|| format!("No \"versions\" array in {:?}", db_path))?;
+    Ok(entries
+        .iter()
+        .filter_map(|entry| {
+            // Exactly one of the version schemes is set on each entry.
+            let version = ["version", "version-semver", "version-date", "version-string"]
+                .iter()
+                .find_map(|key| entry.get(*key).and_then(Value::as_str))?;
+            let port_version = entry
+                .get("port-version")
+                .and_then(Value::as_u64)
+                .unwrap_or(0) as u32;
+            Some(PortVersion {
+                version: version.to_string(),
+                port_version,
+            })
+        })
+        .collect())
+}
+
+/// Where a project's vcpkg baseline is pinned.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum BaselineSource {
+    /// `builtin-baseline` in vcpkg.json.
+    BuiltinBaseline,
+    /// `vcpkg-configuration.default-registry.baseline` embedded in vcpkg.json.
+    EmbeddedConfiguration,
+    /// `default-registry.baseline` in vcpkg-configuration.json.
+    ConfigurationFile,
+}
+
+fn default_registry_baseline(configuration: &Value) -> Option<String> {
+    configuration
+        .get("default-registry")?
+        .get("baseline")?
+        .as_str()
+        .map(String::from)
+}
+
+/// Finds the baseline the project is pinned to, checking `builtin-baseline`
+/// first and then the default registry of the vcpkg configuration.
+pub fn current_baseline(
+    project_root: &Path,
+    manifest: &VcpkgManifest,
+) -> Option<(BaselineSource, String)> {
+    if let Some(baseline) = &manifest.builtin_baseline {
+        return Some((BaselineSource::BuiltinBaseline, baseline.clone()));
+    }
+    if let Some(baseline) = manifest
+        .extra
+        .get("vcpkg-configuration")
+        .and_then(default_registry_baseline)
+    {
+        return Some((BaselineSource::EmbeddedConfiguration, baseline));
+    }
+    fs::read_to_string(project_root.join(VCPKG_CONFIGURATION_FILENAME))
+        .ok()
+        .and_then(|content| serde_json::from_str::<Value>(&content).ok())
+        .and_then(|configuration| default_registry_baseline(&configuration))
+        .map(|baseline| (BaselineSource::ConfigurationFile, baseline))
+}
+
+/// The commit currently checked out in the vcpkg root.
+pub fn checkout_head(paths: &VcpkgPaths) -> Result<String> {
+    run_command_capture(Path::new("git"), &["rev-parse", "HEAD"], Some(&paths.root))
+        .with_context(|| format!("Failed to read the HEAD commit of {:?}", paths.root))
+}
+
+/// Returns whether `commit` exists in the vcpkg checkout.
+pub fn has_commit(paths: &VcpkgPaths, commit: &str) -> bool {
+    let object = format!("{}^{{commit}}", commit);
+    run_command_capture(Path::new("git"), &["cat-file", "-e", &object], Some(&paths.root)).is_ok()
+}
+
+/// Reads `versions/baseline.json` as of `commit` in the vcpkg checkout.
+pub fn baseline_versions(paths: &VcpkgPaths, commit: &str) -> Result<BTreeMap<String, PortVersion>> {
+    let object = format!("{}:versions/baseline.json", commit);
+    let content = run_command_capture(Path::new("git"), &["show", &object], Some(&paths.root))
+        .with_context(|| format!("Failed to read versions/baseline.json at commit {}", commit))?;
+    let baseline: Value = serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse versions/baseline.json at commit {}", commit))?;
+
+    let ports = baseline
+        .get("default")
+        .and_then(Value::as_object)
+        .with_context(|| format!("No \"default\" baseline at commit {}", commit))?;
+    Ok(ports
+        .iter()
+        .filter_map(|(port, entry)| {
+            let version = entry.get("baseline").and_then(Value::as_str)?;
+            let port_version = entry
+                .get("port-version")
+                .and_then(Value::as_u64)
+                .unwrap_or(0) as u32;
+            Some((
+                port.clone(),
+                PortVersion {
+                    version: version.to_string(),
+                    port_version,
+                },
+            ))
+        })
+        .collect())
+}